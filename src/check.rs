@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+
+use miette::Result;
+
+use crate::{element::Element, error::RuntimeError, limits::grow_stack, state::State};
+
+// the elements whose `pattern` attribute is a regular expression
+const PATTERN_ELEMENTS: [&str; 4] = ["matches", "find", "replace-regex", "split-regex"];
+
+// the elements that assign to the variable in their `var` attribute
+const ASSIGNING_ELEMENTS: [&str; 7] = ["set", "const", "loop", "set-field", "inc", "dec", "update"];
+
+// checks the whole program before it's run, so mistakes are reported even in code that's never reached
+//
+// the regular expressions compiled along the way are cached in the state, so they're only compiled once
+pub fn check(element: &Element, state: &mut State) -> Result<()> {
+    // code run by <eval> can also use the enums the program has defined before it
+    let mut enums = state
+        .enums
+        .iter()
+        .map(|(name, definition)| (name.clone(), variants(definition)))
+        .collect();
+    collect_enums(element, &mut enums);
+
+    check_element(element, &enums, state)
+}
+
+fn variants(definition: &Element) -> Vec<String> {
+    definition
+        .children
+        .iter()
+        .filter(|child| child.name.to_lowercase() == "variant")
+        .filter_map(|child| child.attributes.get("name").cloned())
+        .collect()
+}
+
+// the variants of every enum defined anywhere in the program, by the name of the enum
+fn collect_enums(element: &Element, enums: &mut HashMap<String, Vec<String>>) {
+    if element.name.to_lowercase() == "enum"
+        && let Some(name) = element.attributes.get("name")
+    {
+        enums.insert(name.clone(), variants(element));
+    }
+
+    for child in &element.children {
+        grow_stack(|| collect_enums(child, enums));
+    }
+}
+
+fn check_element(
+    element: &Element,
+    enums: &HashMap<String, Vec<String>>,
+    state: &mut State,
+) -> Result<()> {
+    let name = element.name.to_lowercase();
+
+    // quoted code is only data until it's evaluated, and may not be valid code at all
+    if name == "quote" {
+        return Ok(());
+    }
+
+    if PATTERN_ELEMENTS.contains(&name.as_str()) {
+        state
+            .pattern(element)
+            .map_err(|err| RuntimeError::locate(err.into(), element.span))?;
+    }
+
+    if name == "match" {
+        check_match(element, enums)
+            .map_err(|err| RuntimeError::locate(err.into(), element.span))?;
+    }
+
+    check_constants(element)?;
+
+    element.children.iter().try_for_each(|child| {
+        grow_stack(|| check_element(child, enums, state))
+            .map_err(|err| RuntimeError::expanded(err, &child.expansions))
+    })
+}
+
+// reports assignments to constants defined by earlier siblings, which would always fail when they're run
+fn check_constants(element: &Element) -> Result<()> {
+    let mut constants: Vec<&String> = Vec::new();
+
+    for child in &element.children {
+        if let Some(assignment) = constants
+            .iter()
+            .find_map(|constant| find_assignment(child, constant))
+        {
+            let name = assignment
+                .attributes
+                .get("var")
+                .cloned()
+                .unwrap_or_default();
+            return Err(RuntimeError::locate(
+                RuntimeError::ConstantReassignment(name).into(),
+                assignment.span,
+            ));
+        }
+
+        if matches!(child.name.to_lowercase().as_str(), "const" | "freeze")
+            && let Some(name) = child.attributes.get("var")
+        {
+            constants.push(name);
+        }
+    }
+
+    Ok(())
+}
+
+// an element assigning to the variable, skipping functions, since they may be called before the constant is defined,
+// and quoted code, which may never run
+fn find_assignment<'a>(element: &'a Element, variable: &str) -> Option<&'a Element> {
+    let name = element.name.to_lowercase();
+
+    if name == "function" || name == "quote" {
+        return None;
+    }
+
+    if ASSIGNING_ELEMENTS.contains(&name.as_str())
+        && element
+            .attributes
+            .get("var")
+            .is_some_and(|var| var == variable)
+    {
+        return Some(element);
+    }
+
+    element
+        .children
+        .iter()
+        .find_map(|child| grow_stack(|| find_assignment(child, variable)))
+}
+
+// checks that the variants a <match> matches exist, and that it handles all of them
+fn check_match(
+    element: &Element,
+    enums: &HashMap<String, Vec<String>>,
+) -> Result<(), RuntimeError> {
+    let arms = element.children.iter().skip(1);
+    let cases: Vec<&Element> = arms
+        .clone()
+        .filter(|arm| arm.name.to_lowercase() == "case")
+        .collect();
+
+    let referenced: Vec<&str> = cases
+        .iter()
+        .filter_map(|case| case.attributes.get("variant"))
+        .flat_map(|variants| variants.split_whitespace())
+        .collect();
+
+    if referenced.is_empty() {
+        return Ok(());
+    }
+
+    let (name, variants) = match element.attributes.get("enum") {
+        Some(name) => enums
+            .get_key_value(name)
+            .ok_or_else(|| RuntimeError::UndefinedStruct(name.clone()))?,
+
+        None => {
+            let mut candidates = enums.iter().filter(|(_, variants)| {
+                referenced
+                    .iter()
+                    .all(|variant| variants.iter().any(|v| v == variant))
+            });
+
+            match (candidates.next(), candidates.next()) {
+                (Some(candidate), None) => candidate,
+
+                // the variants belong to more than one enum, so it's up to the `enum` attribute to pick one
+                (Some(_), Some(_)) => return Ok(()),
+
+                (None, _) => {
+                    let unknown = referenced.iter().find(|variant| {
+                        !enums
+                            .values()
+                            .any(|variants| variants.iter().any(|v| v == *variant))
+                    });
+
+                    return Err(match unknown {
+                        Some(variant) => RuntimeError::UndefinedVariant {
+                            name: None,
+                            variant: variant.to_string(),
+                        },
+                        None => RuntimeError::invalid_children(
+                            "match",
+                            "cases for the variants of a single enum",
+                        ),
+                    });
+                }
+            }
+        }
+    };
+
+    if let Some(variant) = referenced
+        .iter()
+        .find(|variant| !variants.iter().any(|v| v == *variant))
+    {
+        return Err(RuntimeError::UndefinedVariant {
+            name: Some(name.clone()),
+            variant: variant.to_string(),
+        });
+    }
+
+    let has_default = arms.clone().any(|arm| arm.name.to_lowercase() == "default");
+
+    // only cases without guards and without other attributes are sure to match every value of their variants
+    let unconditional: Vec<&&Element> = cases
+        .iter()
+        .filter(|case| {
+            case.attributes
+                .keys()
+                .all(|attribute| attribute == "variant")
+                && !case
+                    .children
+                    .iter()
+                    .any(|child| child.name.to_lowercase() == "condition")
+        })
+        .collect();
+
+    if has_default || unconditional.iter().any(|case| case.attributes.is_empty()) {
+        return Ok(());
+    }
+
+    let missing: Vec<String> = variants
+        .iter()
+        .filter(|variant| {
+            !unconditional.iter().any(|case| {
+                case.attributes
+                    .get("variant")
+                    .is_some_and(|v| v.split_whitespace().any(|v| v == *variant))
+            })
+        })
+        .cloned()
+        .collect();
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(RuntimeError::NonExhaustiveMatch {
+            name: name.clone(),
+            missing,
+        })
+    }
+}
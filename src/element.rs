@@ -1,8 +1,103 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+};
 
-#[derive(Debug, Clone)]
+use miette::SourceSpan;
+use quick_xml::escape::escape;
+
+use crate::limits::grow_stack;
+
+#[derive(Debug)]
 pub struct Element {
     pub name: String,
+    // the namespace prefix of the name, like `fn` in `<fn:greet>`
+    pub prefix: Option<String>,
     pub attributes: HashMap<String, String>,
     pub children: Vec<Element>,
+    pub span: SourceSpan,
+    // the uses of macros that produced the element, from the innermost to the outermost
+    pub expansions: Vec<Expansion>,
+}
+
+// a use of a macro, which is replaced by the body of the macro before the program runs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expansion {
+    pub name: String,
+    pub span: SourceSpan,
+}
+
+impl Element {
+    // text nodes have no name, and keep their text in the `_text` attribute
+    pub fn text(&self) -> Option<&str> {
+        if self.name.is_empty() {
+            self.attributes.get("_text").map(String::as_str)
+        } else {
+            None
+        }
+    }
+
+    // points the element and all of its descendants at the given span, for code that doesn't come from the source file
+    pub fn relocate(&mut self, span: SourceSpan) {
+        self.span = span;
+        for child in &mut self.children {
+            grow_stack(|| child.relocate(span));
+        }
+    }
+}
+
+// cloning recurses into the children, so it has to grow the stack like everything else that walks the tree
+impl Clone for Element {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            prefix: self.prefix.clone(),
+            attributes: self.attributes.clone(),
+            children: grow_stack(|| self.children.clone()),
+            span: self.span,
+            expansions: self.expansions.clone(),
+        }
+    }
+}
+
+// elements are equal if they'd be written the same way, no matter where they're written
+impl PartialEq for Element {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.prefix == other.prefix
+            && self.attributes == other.attributes
+            && grow_stack(|| self.children == other.children)
+    }
+}
+
+// writes the element back as XML
+impl Display for Element {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(text) = self.text() {
+            return write!(f, "{}", escape(text));
+        }
+
+        let name = match &self.prefix {
+            Some(prefix) => format!("{prefix}:{}", self.name),
+            None => self.name.clone(),
+        };
+
+        write!(f, "<{name}")?;
+
+        let mut attributes = self.attributes.iter().collect::<Vec<_>>();
+        attributes.sort();
+        for (name, value) in attributes {
+            write!(f, " {name}=\"{}\"", escape(value))?;
+        }
+
+        if self.children.is_empty() {
+            return write!(f, " />");
+        }
+
+        write!(f, ">")?;
+        for child in &self.children {
+            grow_stack(|| write!(f, "{child}"))?;
+        }
+        write!(f, "</{name}>")
+    }
 }
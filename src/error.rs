@@ -0,0 +1,391 @@
+use std::{
+    error::Error,
+    fmt::{Display, Formatter},
+    io,
+};
+
+use miette::{Diagnostic, LabeledSpan, Report, Severity, SourceCode, SourceSpan};
+
+use crate::{element::Expansion, limits::LimitExceeded, value::Value};
+
+// every error raised by the interpreter itself, as opposed to the ones reported by the XML parser
+//
+// the codes are stable, so they can be relied on in `<catch>` and in tests
+#[derive(Debug, thiserror::Error, Diagnostic)]
+pub enum RuntimeError {
+    #[error("Root element must be <program>")]
+    #[diagnostic(
+        code(xmlang::invalid_root),
+        help("Wrap the whole program in a <program> element")
+    )]
+    InvalidRoot,
+
+    #[error("Unknown element: {0}")]
+    #[diagnostic(
+        code(xmlang::unknown_element),
+        help("Check the spelling of the element name, or define a <function> with this name")
+    )]
+    UnknownElement(String),
+
+    #[error("Expected {expected} in <{element}> element")]
+    #[diagnostic(code(xmlang::invalid_children))]
+    InvalidChildren { element: String, expected: String },
+
+    #[error("Unexpected child in <{element}> element: {child}")]
+    #[diagnostic(code(xmlang::unexpected_child))]
+    UnexpectedChild { element: String, child: String },
+
+    #[error("Expected the `{attribute}` attribute in <{element}> element")]
+    #[diagnostic(code(xmlang::missing_attribute))]
+    MissingAttribute { element: String, attribute: String },
+
+    #[error("Invalid value `{value}` of the `{attribute}` attribute in <{element}> element")]
+    #[diagnostic(code(xmlang::invalid_attribute))]
+    InvalidAttribute {
+        element: String,
+        attribute: String,
+        value: String,
+    },
+
+    #[error("Failed to convert value to {target}")]
+    #[diagnostic(
+        code(xmlang::conversion_failed),
+        help("The {} `{value}` can't be converted to {target}", value.type_name())
+    )]
+    ConversionFailed { value: Value, target: &'static str },
+
+    #[error("Can't {operation} incompatible type{}: {}{}", if .b.is_some() { "s" } else { "" }, .a.type_name(), if let Some(right) = .b { format!(" and {}", right.type_name()) } else { String::new() })]
+    #[diagnostic(code(xmlang::type_mismatch))]
+    IncompatibleTypes {
+        operation: String,
+        a: Value,
+        b: Option<Value>,
+    },
+
+    #[error("Division by zero is not allowed")]
+    #[diagnostic(code(xmlang::division_by_zero))]
+    DivisionByZero,
+
+    #[error("Integer overflow while trying to {0}")]
+    #[diagnostic(
+        code(xmlang::integer_overflow),
+        help(
+            "Use `mode=\"promote\"` to get a big integer instead, `mode=\"wrapping\"` or `mode=\"saturating\"` to handle overflow differently, or `--overflow promote` to promote every overflowing int"
+        )
+    )]
+    IntegerOverflow(String),
+
+    #[error("Decimal overflow while trying to {0}")]
+    #[diagnostic(
+        code(xmlang::decimal_overflow),
+        help("Decimals can only hold numbers up to about 7.9 × 10²⁸")
+    )]
+    DecimalOverflow(String),
+
+    #[error("Function `{0}` not found")]
+    #[diagnostic(
+        code(xmlang::undefined_function),
+        help("Functions have to be defined with <function> before they're called")
+    )]
+    UndefinedFunction(String),
+
+    #[error("Struct or enum `{0}` not found")]
+    #[diagnostic(
+        code(xmlang::undefined_struct),
+        help("Structs and enums have to be defined with <struct> or <enum> before they're used")
+    )]
+    UndefinedStruct(String),
+
+    #[error("Variant `{variant}` not found{}", name.as_ref().map(|name| format!(" in enum `{name}`")).unwrap_or_default())]
+    #[diagnostic(code(xmlang::undefined_variant))]
+    UndefinedVariant {
+        name: Option<String>,
+        variant: String,
+    },
+
+    #[error("Non-exhaustive <match>: the {} `{}` of enum `{name}` {} handled", if missing.len() == 1 { "variant" } else { "variants" }, missing.join("`, `"), if missing.len() == 1 { "isn't" } else { "aren't" })]
+    #[diagnostic(
+        code(xmlang::non_exhaustive_match),
+        help("Add a <case> for each variant, or a <default>")
+    )]
+    NonExhaustiveMatch { name: String, missing: Vec<String> },
+
+    #[error("`{name}` has no field `{field}`")]
+    #[diagnostic(code(xmlang::undefined_field))]
+    UndefinedField { name: String, field: String },
+
+    #[error("Missing value for the field `{field}` of `{name}`")]
+    #[diagnostic(
+        code(xmlang::missing_field),
+        help("Give the field a value in <new>, or a default value in its definition")
+    )]
+    MissingField { name: String, field: String },
+
+    #[error("The field `{field}` of `{name}` must be of type {expected}, but got the {} `{value}`", value.type_name())]
+    #[diagnostic(code(xmlang::invalid_field_type))]
+    InvalidFieldType {
+        name: String,
+        field: String,
+        expected: String,
+        value: Value,
+    },
+
+    #[error("Missing value for the slot `{slot}` of macro `{name}`")]
+    #[diagnostic(
+        code(xmlang::missing_slot),
+        help(
+            "Give the slot a value with an attribute or a child element of the same name, or a default value in the macro"
+        )
+    )]
+    MissingSlot { name: String, slot: String },
+
+    #[error("Macro `{0}` is expanded too many times inside itself")]
+    #[diagnostic(
+        code(xmlang::macro_recursion),
+        help(
+            "Macros are expanded before the program runs, so they can't stop recursing based on runtime values; use a <function> instead"
+        )
+    )]
+    MacroRecursion(String),
+
+    #[error("Invalid XML in <eval> element: {0}")]
+    #[diagnostic(code(xmlang::invalid_xml))]
+    InvalidXml(String),
+
+    #[error("Variable `{0}` not found")]
+    #[diagnostic(
+        code(xmlang::undefined_variable),
+        help("Variables have to be defined with <set> before they're updated")
+    )]
+    UndefinedVariable(String),
+
+    #[error("Can't assign to `{0}`, because it's a constant")]
+    #[diagnostic(
+        code(xmlang::constant_reassignment),
+        help("Variables defined with <const> or frozen with <freeze> can't be changed")
+    )]
+    ConstantReassignment(String),
+
+    #[error("Special `{0}` not found")]
+    #[diagnostic(code(xmlang::undefined_special))]
+    UndefinedSpecial(String),
+
+    #[error("{0}")]
+    #[diagnostic(code(xmlang::null_unwrapped))]
+    NullUnwrapped(String),
+
+    #[error("`min` must be less than `max` in <{0}> element")]
+    #[diagnostic(code(xmlang::invalid_range))]
+    InvalidRange(String),
+
+    #[error("Invalid template in <format> element: {0}")]
+    #[diagnostic(code(xmlang::invalid_format))]
+    InvalidFormat(String),
+
+    #[error("Placeholder `{{{0}}}` doesn't match any variable, special or child")]
+    #[diagnostic(
+        code(xmlang::undefined_placeholder),
+        help("Use `{{{{` and `}}}}` to insert literal braces")
+    )]
+    UndefinedPlaceholder(String),
+
+    #[error("Invalid regular expression `{pattern}`")]
+    #[diagnostic(code(xmlang::invalid_pattern), help("{message}"))]
+    InvalidPattern { pattern: String, message: String },
+
+    #[error("Tried to continue outside of a loop")]
+    #[diagnostic(
+        code(xmlang::continue_outside_loop),
+        help("<continue /> only skips to the next iteration of loops in the same function")
+    )]
+    ContinueOutsideLoop,
+
+    #[error("Tried to rethrow outside of a <catch> element")]
+    #[diagnostic(code(xmlang::rethrow_outside_catch))]
+    RethrowOutsideCatch,
+
+    #[error("{message}")]
+    #[diagnostic(code(xmlang::thrown))]
+    Thrown {
+        message: String,
+        kind: String,
+        data: Value,
+    },
+
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    LimitExceeded(#[from] LimitExceeded),
+
+    #[error("Failed to access the standard input or output")]
+    #[diagnostic(code(xmlang::io))]
+    Io(#[from] io::Error),
+}
+
+impl RuntimeError {
+    pub fn invalid_children(element: impl Into<String>, expected: impl Into<String>) -> Self {
+        Self::InvalidChildren {
+            element: element.into(),
+            expected: expected.into(),
+        }
+    }
+
+    pub fn unexpected_child(element: impl Into<String>, child: impl Into<String>) -> Self {
+        Self::UnexpectedChild {
+            element: element.into(),
+            child: child.into(),
+        }
+    }
+
+    pub fn missing_attribute(element: impl Into<String>, attribute: impl Into<String>) -> Self {
+        Self::MissingAttribute {
+            element: element.into(),
+            attribute: attribute.into(),
+        }
+    }
+
+    pub fn conversion_failed(value: &Value, target: &'static str) -> Self {
+        Self::ConversionFailed {
+            value: value.clone(),
+            target,
+        }
+    }
+
+    // the name `<catch kind="...">` matches errors against
+    pub fn kind(&self) -> &str {
+        match self {
+            Self::InvalidRoot => "InvalidRoot",
+            Self::UnknownElement(_) => "UnknownElement",
+            Self::InvalidChildren { .. } => "InvalidChildren",
+            Self::UnexpectedChild { .. } => "UnexpectedChild",
+            Self::MissingAttribute { .. } => "MissingAttribute",
+            Self::InvalidAttribute { .. } => "InvalidAttribute",
+            Self::ConversionFailed { .. } => "ConversionFailed",
+            Self::IncompatibleTypes { .. } => "IncompatibleTypes",
+            Self::DivisionByZero => "DivisionByZero",
+            Self::IntegerOverflow(_) => "IntegerOverflow",
+            Self::DecimalOverflow(_) => "DecimalOverflow",
+            Self::UndefinedFunction(_) => "UndefinedFunction",
+            Self::UndefinedSpecial(_) => "UndefinedSpecial",
+            Self::MissingSlot { .. } => "MissingSlot",
+            Self::MacroRecursion(_) => "MacroRecursion",
+            Self::InvalidXml(_) => "InvalidXml",
+            Self::UndefinedVariable(_) => "UndefinedVariable",
+            Self::ConstantReassignment(_) => "ConstantReassignment",
+            Self::UndefinedStruct(_) => "UndefinedStruct",
+            Self::UndefinedVariant { .. } => "UndefinedVariant",
+            Self::NonExhaustiveMatch { .. } => "NonExhaustiveMatch",
+            Self::UndefinedField { .. } => "UndefinedField",
+            Self::MissingField { .. } => "MissingField",
+            Self::InvalidFieldType { .. } => "InvalidFieldType",
+            Self::NullUnwrapped(_) => "NullUnwrapped",
+            Self::InvalidRange(_) => "InvalidRange",
+            Self::InvalidFormat(_) => "InvalidFormat",
+            Self::UndefinedPlaceholder(_) => "UndefinedPlaceholder",
+            Self::InvalidPattern { .. } => "InvalidPattern",
+            Self::ContinueOutsideLoop => "ContinueOutsideLoop",
+            Self::RethrowOutsideCatch => "RethrowOutsideCatch",
+            Self::Thrown { kind, .. } => kind,
+            Self::LimitExceeded(_) => "LimitExceeded",
+            Self::Io(_) => "Io",
+        }
+    }
+
+    // finds the runtime error in a report, whether it's been located yet or not
+    pub fn find(err: &Report) -> Option<&Self> {
+        err.downcast_ref::<Self>()
+            .or_else(|| err.downcast_ref::<LocatedError>().map(|err| &err.error))
+    }
+
+    // attaches the span of the element that raised the error, unless it already has one
+    pub fn locate(err: Report, span: SourceSpan) -> Report {
+        let error = match err.downcast::<Self>() {
+            Ok(error) => error,
+            Err(err) => match err.downcast::<LimitExceeded>() {
+                Ok(limit) => Self::LimitExceeded(limit),
+                Err(err) => return err,
+            },
+        };
+
+        LocatedError {
+            error,
+            span,
+            expansions: Vec::new(),
+        }
+        .into()
+    }
+
+    // points out the uses of macros that produced the element that raised the error, each only once, and only if it isn't the element itself
+    pub fn expanded(err: Report, expansions: &[Expansion]) -> Report {
+        if expansions.is_empty() {
+            return err;
+        }
+
+        match err.downcast::<LocatedError>() {
+            Ok(mut located) => {
+                for expansion in expansions {
+                    if expansion.span != located.span && !located.expansions.contains(expansion) {
+                        located.expansions.push(expansion.clone());
+                    }
+                }
+                located.into()
+            }
+            Err(err) => err,
+        }
+    }
+}
+
+// a runtime error pointing at the element that raised it
+#[derive(Debug)]
+pub struct LocatedError {
+    error: RuntimeError,
+    span: SourceSpan,
+    // from the innermost to the outermost
+    expansions: Vec<Expansion>,
+}
+
+impl Display for LocatedError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl Error for LocatedError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.error.source()
+    }
+}
+
+impl Diagnostic for LocatedError {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.error.code()
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        self.error.severity()
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.error.help()
+    }
+
+    fn url<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.error.url()
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        self.error.source_code()
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        Some(Box::new(
+            std::iter::once(LabeledSpan::underline(self.span)).chain(self.expansions.iter().map(
+                |expansion| {
+                    LabeledSpan::at(
+                        expansion.span,
+                        format!("in the expansion of <{}>", expansion.name),
+                    )
+                },
+            )),
+        ))
+    }
+}
@@ -0,0 +1,279 @@
+use miette::Result;
+use num_traits::Signed;
+
+use crate::{error::RuntimeError, state::State, value::Value};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+// a parsed `[[fill]align][+][#][0][width][.precision][type]` format spec, like in Rust's `format!`
+#[derive(Debug, Clone, Default)]
+struct Spec {
+    fill: Option<char>,
+    align: Option<Align>,
+    sign: bool,
+    alternate: bool,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+    kind: Option<char>,
+}
+
+impl Spec {
+    fn parse(spec: &str) -> Option<Self> {
+        let mut result = Self::default();
+        let mut chars = spec.chars().peekable();
+
+        let align = |c| match c {
+            '<' => Some(Align::Left),
+            '>' => Some(Align::Right),
+            '^' => Some(Align::Center),
+            _ => None,
+        };
+
+        let mut lookahead = spec.chars();
+        match (lookahead.next(), lookahead.next()) {
+            (Some(fill), Some(second)) if align(second).is_some() => {
+                result.fill = Some(fill);
+                result.align = align(second);
+                chars.next();
+                chars.next();
+            }
+            (Some(first), _) if align(first).is_some() => {
+                result.align = align(first);
+                chars.next();
+            }
+            _ => {}
+        }
+
+        if chars.next_if_eq(&'+').is_some() {
+            result.sign = true;
+        }
+        if chars.next_if_eq(&'#').is_some() {
+            result.alternate = true;
+        }
+        if chars.next_if_eq(&'0').is_some() {
+            result.zero = true;
+        }
+
+        let mut width = String::new();
+        while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+            width.push(digit);
+        }
+        if !width.is_empty() {
+            result.width = width.parse().ok()?;
+        }
+
+        if chars.next_if_eq(&'.').is_some() {
+            let mut precision = String::new();
+            while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                precision.push(digit);
+            }
+            result.precision = Some(precision.parse().ok()?);
+        }
+
+        if let Some(kind) = chars.next_if(|c| matches!(c, 'x' | 'X' | 'o' | 'b' | 'e')) {
+            result.kind = Some(kind);
+        }
+
+        chars.next().is_none().then_some(result)
+    }
+
+    fn apply(&self, value: &Value) -> Result<String, RuntimeError> {
+        let (sign, prefix, body) = match (value, self.kind) {
+            (Value::Int(i), Some(kind @ ('x' | 'X' | 'o' | 'b'))) => {
+                let magnitude = i.unsigned_abs();
+                let (prefix, body) = match kind {
+                    'x' => ("0x", format!("{magnitude:x}")),
+                    'X' => ("0x", format!("{magnitude:X}")),
+                    'o' => ("0o", format!("{magnitude:o}")),
+                    _ => ("0b", format!("{magnitude:b}")),
+                };
+
+                (
+                    self.sign_of(*i < 0),
+                    if self.alternate { prefix } else { "" },
+                    body,
+                )
+            }
+
+            (Value::Int(i), None) => (self.sign_of(*i < 0), "", i.unsigned_abs().to_string()),
+
+            (Value::BigInt(i), Some(kind @ ('x' | 'X' | 'o' | 'b'))) => {
+                let magnitude = i.magnitude();
+                let (prefix, body) = match kind {
+                    'x' => ("0x", format!("{magnitude:x}")),
+                    'X' => ("0x", format!("{magnitude:X}")),
+                    'o' => ("0o", format!("{magnitude:o}")),
+                    _ => ("0b", format!("{magnitude:b}")),
+                };
+
+                (
+                    self.sign_of(i.is_negative()),
+                    if self.alternate { prefix } else { "" },
+                    body,
+                )
+            }
+
+            (Value::BigInt(i), None) => {
+                (self.sign_of(i.is_negative()), "", i.magnitude().to_string())
+            }
+
+            (Value::Decimal(d), None) => {
+                let magnitude = d.abs();
+                let body = match self.precision {
+                    Some(precision) => format!("{magnitude:.precision$}"),
+                    None => magnitude.to_string(),
+                };
+
+                (self.sign_of(d.is_sign_negative() && !d.is_zero()), "", body)
+            }
+
+            (Value::Float(f), None | Some('e')) => {
+                let magnitude = f.abs();
+                let body = match (self.kind, self.precision) {
+                    (Some(_), Some(precision)) => format!("{magnitude:.precision$e}"),
+                    (Some(_), None) => format!("{magnitude:e}"),
+                    (None, Some(precision)) => format!("{magnitude:.precision$}"),
+                    (None, None) => magnitude.to_string(),
+                };
+
+                (self.sign_of(f.is_sign_negative() && *f != 0.0), "", body)
+            }
+
+            (Value::Int(_) | Value::BigInt(_) | Value::Decimal(_), Some('e')) => {
+                return self.apply(&Value::Float(value.as_float().unwrap_or(f64::NAN)));
+            }
+
+            (_, Some(kind)) => {
+                return Err(RuntimeError::InvalidFormat(format!(
+                    "`{kind}` can't be used to format a value of type {}",
+                    value.type_name()
+                )));
+            }
+
+            (value, None) => {
+                let text = value.to_string();
+                let text = match self.precision {
+                    Some(precision) => text.chars().take(precision).collect(),
+                    None => text,
+                };
+
+                ("", "", text)
+            }
+        };
+
+        let numeric = matches!(
+            value,
+            Value::Int(_) | Value::Float(_) | Value::BigInt(_) | Value::Decimal(_)
+        );
+        let length = sign.chars().count() + prefix.chars().count() + body.chars().count();
+        let padding = self.width.saturating_sub(length);
+
+        if self.zero && numeric && self.align.is_none() {
+            return Ok(format!("{sign}{prefix}{}{body}", "0".repeat(padding)));
+        }
+
+        let fill = self.fill.unwrap_or(' ').to_string();
+        let align = self
+            .align
+            .unwrap_or(if numeric { Align::Right } else { Align::Left });
+
+        let (before, after) = match align {
+            Align::Left => (0, padding),
+            Align::Right => (padding, 0),
+            Align::Center => (padding / 2, padding - padding / 2),
+        };
+
+        Ok(format!(
+            "{}{sign}{prefix}{body}{}",
+            fill.repeat(before),
+            fill.repeat(after)
+        ))
+    }
+
+    fn sign_of(&self, negative: bool) -> &'static str {
+        if negative {
+            "-"
+        } else if self.sign {
+            "+"
+        } else {
+            ""
+        }
+    }
+}
+
+// replaces every `{name}` or `{name:spec}` placeholder in the template with the value `lookup` finds for it
+//
+// since names like `child:0` contain colons too, the whole placeholder is tried as a name before splitting off the spec
+pub fn interpolate(
+    template: &str,
+    state: &State,
+    mut lookup: impl FnMut(&str) -> Option<Value>,
+) -> Result<String> {
+    let mut output = String::new();
+    let mut chars = template.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.as_str().starts_with('{') => {
+                chars.next();
+                output.push('{');
+            }
+
+            '}' if chars.as_str().starts_with('}') => {
+                chars.next();
+                output.push('}');
+            }
+
+            '{' => {
+                let rest = chars.as_str();
+                let end = rest
+                    .find('}')
+                    .ok_or_else(|| RuntimeError::InvalidFormat("unclosed `{`".to_string()))?;
+
+                let placeholder = &rest[..end];
+                chars = rest[end + 1..].chars();
+
+                if let Some(value) = lookup(placeholder) {
+                    output.push_str(&value.to_string());
+                    continue;
+                }
+
+                let (name, spec) = placeholder
+                    .rsplit_once(':')
+                    .ok_or_else(|| RuntimeError::UndefinedPlaceholder(placeholder.to_string()))?;
+
+                let value = lookup(name)
+                    .ok_or_else(|| RuntimeError::UndefinedPlaceholder(name.to_string()))?;
+
+                let spec = Spec::parse(spec).ok_or_else(|| {
+                    RuntimeError::InvalidFormat(format!("invalid format spec `{spec}`"))
+                })?;
+
+                // the width and the precision of numbers pad the value, so they're checked before the padding is allocated
+                state.check_string_length(spec.width)?;
+                if matches!(value, Value::Float(_) | Value::Decimal(_)) || spec.kind == Some('e') {
+                    state.check_string_length(spec.precision.unwrap_or_default())?;
+                }
+
+                output.push_str(&spec.apply(&value)?);
+            }
+
+            '}' => {
+                return Err(RuntimeError::InvalidFormat(
+                    "unmatched `}`, use `}}` to insert a literal `}`".to_string(),
+                )
+                .into());
+            }
+
+            c => output.push(c),
+        }
+    }
+
+    Ok(output)
+}
@@ -1,16 +1,35 @@
 use std::{
+    cmp::Ordering,
     collections::HashMap,
     io::{self, Write},
-    thread::sleep,
+    str::FromStr,
     time::Duration,
 };
 
-use miette::{Context, Diagnostic, IntoDiagnostic, Report, Result, bail, ensure};
+use miette::{Diagnostic, Report, Result, SourceSpan, bail, ensure};
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive};
+use rand::{
+    Rng,
+    seq::{IndexedRandom, SliceRandom},
+};
+use rust_decimal::{Decimal, MathematicalOps, RoundingStrategy};
 use thiserror::Error;
+use unicode_segmentation::UnicodeSegmentation;
 
 use crate::{
+    check::check,
     element::Element,
-    value::{Abs, Value},
+    error::RuntimeError,
+    format::interpolate,
+    limits::grow_stack,
+    macros::expand,
+    parser::{ParseError, parse},
+    specials::Specials,
+    stack::CallFrame,
+    state::State,
+    value::{Overflow, Value, Variant},
+    variables::Variables,
 };
 
 // hacky!
@@ -18,7 +37,9 @@ use crate::{
 enum BlockControl {
     #[error("")] // this should never be seen by the user
     Break(Value),
-    #[error("Tried to continue outside of a loop")]
+    #[error("")]
+    BreakLoop(Value),
+    #[error("")]
     Continue,
 }
 
@@ -28,28 +49,560 @@ impl Default for BlockControl {
     }
 }
 
+// the name `<catch kind="...">` matches errors against
+fn error_kind(err: &Report) -> String {
+    if let Some(err) = RuntimeError::find(err) {
+        err.kind().to_string()
+    } else {
+        "RuntimeError".to_string()
+    }
+}
+
+// runs the children of a block in order, returning the value of the last one
+fn run_block(
+    block: &Element,
+    depth: u32,
+    variables: &mut Variables,
+    specials: &Specials,
+    state: &mut State,
+) -> Result<Value> {
+    block.children.iter().try_fold(Value::Null, |_, child| {
+        interpret(child, depth + 1, variables, specials, state)
+    })
+}
+
 // TODO: include code snippets with errors
 pub fn interpret(
     element: &Element,
     depth: u32,
-    variables: &mut HashMap<String, Value>,
-    specials: &[HashMap<String, Value>],
-    functions: &mut HashMap<String, Vec<Element>>,
+    variables: &mut Variables,
+    specials: &Specials,
+    state: &mut State,
+) -> Result<Value> {
+    grow_stack(|| {
+        state.tick()?;
+
+        let value = evaluate(element, depth, variables, specials, state)?;
+
+        if let Value::Str(text) = &value {
+            state.check_string_length(text.len())?;
+        }
+
+        Ok(value)
+    })
+    .map_err(|err| {
+        RuntimeError::expanded(RuntimeError::locate(err, element.span), &element.expansions)
+    })
+}
+
+// the length of the string that multiplying `a` by `b` would produce, so it can be checked before allocating it
+fn repeated_length(a: &Value, b: &Value) -> Option<usize> {
+    match (a, b) {
+        (Value::Str(s), Value::Int(i)) | (Value::Int(i), Value::Str(s)) => {
+            Some(s.len().saturating_mul(i.unsigned_abs() as usize))
+        }
+        (Value::Str(s), Value::Float(f)) | (Value::Float(f), Value::Str(s)) => {
+            Some(s.len().saturating_mul(f.abs() as usize))
+        }
+        _ => None,
+    }
+}
+
+// strings are indexed by grapheme clusters, so that `<length>` of "é" or "👍🏽" is 1
+fn graphemes(text: &str) -> Vec<&str> {
+    text.graphemes(true).collect()
+}
+
+// turns a possibly negative index (counted from the end) into one within `0..=length`
+fn resolve_index(index: i64, length: usize) -> usize {
+    if index < 0 {
+        length.saturating_sub(index.unsigned_abs() as usize)
+    } else {
+        (index as usize).min(length)
+    }
+}
+
+// evaluates the children of a string operation: the first one is the value to operate on,
+// and the following ones are its arguments, which can also be given as attributes
+fn operands(
+    element: &Element,
+    name: &str,
+    arguments: &[&str],
+    depth: u32,
+    variables: &mut Variables,
+    specials: &Specials,
+    state: &mut State,
+) -> Result<(Value, Vec<Option<Value>>)> {
+    ensure!(
+        !element.children.is_empty() && element.children.len() <= arguments.len() + 1,
+        RuntimeError::invalid_children(
+            name,
+            if arguments.is_empty() {
+                "exactly one child".to_string()
+            } else {
+                format!("between 1 and {} children", arguments.len() + 1)
+            }
+        )
+    );
+
+    let mut values = element
+        .children
+        .iter()
+        .map(|child| interpret(child, depth + 1, variables, specials, state))
+        .collect::<Result<Vec<Value>>>()?
+        .into_iter();
+
+    let value = values.next().unwrap_or_default();
+    let arguments = arguments
+        .iter()
+        .map(|attribute| {
+            values.next().or_else(|| {
+                element
+                    .attributes
+                    .get(*attribute)
+                    .map(|s| Value::from(s.as_str()))
+            })
+        })
+        .collect();
+
+    Ok((value, arguments))
+}
+
+fn int_operand(value: Option<Value>) -> Result<Option<i64>, RuntimeError> {
+    value
+        .map(|value| {
+            value
+                .as_int()
+                .ok_or_else(|| RuntimeError::conversion_failed(&value, "an integer"))
+        })
+        .transpose()
+}
+
+// the `mode` attribute of an arithmetic element, falling back to the interpreter-wide setting
+fn overflow_mode(element: &Element, state: &State) -> Result<Overflow, RuntimeError> {
+    element
+        .attributes
+        .get("mode")
+        .map_or(Ok(state.overflow), |mode| {
+            mode.parse().map_err(|_| RuntimeError::InvalidAttribute {
+                element: element.name.clone(),
+                attribute: "mode".to_string(),
+                value: mode.clone(),
+            })
+        })
+}
+
+// parses an integer written in the given base, with an optional sign and a `0x`, `0o` or `0b` prefix matching the base
+fn parse_radix(element: &Element, text: &str) -> Result<Option<BigInt>, RuntimeError> {
+    let base = base_attribute(element)?.unwrap_or(10);
+
+    let text = text.trim();
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+
+    let prefix = match base {
+        2 => Some("0b"),
+        8 => Some("0o"),
+        16 => Some("0x"),
+        _ => None,
+    };
+    let digits = prefix
+        .and_then(|prefix| {
+            digits
+                .get(..2)
+                .filter(|start| start.eq_ignore_ascii_case(prefix))
+                .map(|_| &digits[2..])
+        })
+        .unwrap_or(digits);
+
+    if digits.is_empty() || digits.starts_with(['+', '-']) {
+        return Ok(None);
+    }
+
+    Ok(BigInt::parse_bytes(digits.as_bytes(), base)
+        .map(|value| if negative { -value } else { value }))
+}
+
+fn base_attribute(element: &Element) -> Result<Option<u32>, RuntimeError> {
+    element
+        .attributes
+        .get("base")
+        .map(|base| {
+            base.parse::<u32>()
+                .ok()
+                .filter(|base| (2..=36).contains(base))
+                .ok_or_else(|| RuntimeError::InvalidAttribute {
+                    element: element.name.clone(),
+                    attribute: "base".to_string(),
+                    value: base.clone(),
+                })
+        })
+        .transpose()
+}
+
+// an attribute holding a number, which is an error if it's there but isn't a number
+fn number_attribute<T: FromStr>(
+    element: &Element,
+    attribute: &str,
+) -> Result<Option<T>, RuntimeError> {
+    element
+        .attributes
+        .get(attribute)
+        .map(|value| {
+            value.parse().map_err(|_| RuntimeError::InvalidAttribute {
+                element: element.name.clone(),
+                attribute: attribute.to_string(),
+                value: value.clone(),
+            })
+        })
+        .transpose()
+}
+
+fn float_operand(value: &Value) -> Result<f64, RuntimeError> {
+    value
+        .as_float()
+        .ok_or_else(|| RuntimeError::conversion_failed(value, "a float"))
+}
+
+// applies an operation of <inc>, <dec> or <update> to a variable, changing it in place
+//
+// strings and lists are appended to in place, so accumulating them doesn't copy what's already there
+fn update_variable(
+    variables: &mut Variables,
+    state: &State,
+    name: &str,
+    operation: &str,
+    operand: Value,
+    overflow: Overflow,
+) -> Result<()> {
+    let slot = variables
+        .get_mut(name)?
+        .ok_or_else(|| RuntimeError::UndefinedVariable(name.to_string()))?;
+
+    match (operation, &mut *slot, operand) {
+        ("add" | "concat", Value::Str(text), operand) => {
+            let operand = operand.to_string();
+            state.check_string_length(text.len() + operand.len())?;
+            text.push_str(&operand);
+        }
+        ("add" | "concat", Value::List(items), Value::List(operand)) => items.extend(operand),
+        ("add" | "concat", Value::List(items), operand) => items.push(operand),
+
+        ("concat", value, operand) => {
+            let text = format!("{value}{operand}");
+            state.check_string_length(text.len())?;
+            *value = Value::Str(text);
+        }
+
+        (operation, value, operand) => {
+            // the operations take their operands by value, so the variable is moved out of its slot, with a
+            // copy to put back if the operation fails. appending to a string or list is handled above, so
+            // this never copies an accumulator that keeps growing
+            let current = std::mem::take(value);
+            let old = current.clone();
+
+            let result = match operation {
+                "add" => current.add(operand, overflow),
+                "sub" => current.sub(operand, overflow),
+                "mul" => current.mul(operand, overflow),
+                "div" => current.div(operand, overflow),
+                _ => current.rem(operand),
+            };
+
+            match result {
+                Ok(new) => *value = new,
+                Err(err) => {
+                    *value = old;
+                    return Err(err.into());
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// a `start`, `end` or `step` of <loop>, given either as an attribute or as a child element evaluated once before the loop
+fn loop_bound(
+    element: &Element,
+    bound: &str,
+    depth: u32,
+    variables: &mut Variables,
+    specials: &Specials,
+    state: &mut State,
+) -> Result<Option<i64>> {
+    let children: Vec<&Element> = element
+        .children
+        .iter()
+        .filter(|child| child.name.to_lowercase() == bound)
+        .collect();
+
+    match (element.attributes.get(bound), children.as_slice()) {
+        (None, []) => Ok(None),
+
+        (Some(value), []) => Ok(Some(value.parse::<i64>().map_err(|_| {
+            RuntimeError::InvalidAttribute {
+                element: "loop".to_string(),
+                attribute: bound.to_string(),
+                value: value.clone(),
+            }
+        })?)),
+
+        (None, [child]) => {
+            ensure!(
+                child.children.len() == 1,
+                RuntimeError::invalid_children(bound, "exactly one child")
+            );
+
+            let value = interpret(&child.children[0], depth + 2, variables, specials, state)?;
+            Ok(int_operand(Some(value))?)
+        }
+
+        _ => bail!(RuntimeError::invalid_children(
+            "loop",
+            format!("the `{bound}` attribute or one <{bound}> child, but not both")
+        )),
+    }
+}
+
+// the element listing the fields of a struct, or of a variant of an enum
+fn definition<'a>(
+    state: &'a State,
+    name: &str,
+    variant: Option<&str>,
+) -> Result<&'a Element, RuntimeError> {
+    let Some(variant) = variant else {
+        return state
+            .structs
+            .get(name)
+            .ok_or_else(|| RuntimeError::UndefinedStruct(name.to_string()));
+    };
+
+    state
+        .enums
+        .get(name)
+        .ok_or_else(|| RuntimeError::UndefinedStruct(name.to_string()))?
+        .children
+        .iter()
+        .find(|child| child.attributes.get("name").is_some_and(|n| n == variant))
+        .ok_or_else(|| RuntimeError::UndefinedVariant {
+            name: Some(name.to_string()),
+            variant: variant.to_string(),
+        })
+}
+
+// checks the <field> children of a <struct> or <variant> element
+fn check_fields(element: &Element) -> Result<()> {
+    let mut fields = Vec::new();
+
+    for child in &element.children {
+        ensure!(
+            child.name.to_lowercase() == "field",
+            RuntimeError::unexpected_child(&element.name, &child.name)
+        );
+
+        let field = child
+            .attributes
+            .get("name")
+            .ok_or_else(|| RuntimeError::missing_attribute("field", "name"))?;
+
+        ensure!(
+            !fields.contains(&field),
+            RuntimeError::invalid_children(&element.name, "fields with different names")
+        );
+        ensure!(
+            child.children.len() <= 1,
+            RuntimeError::invalid_children("field", "at most one child")
+        );
+
+        fields.push(field);
+    }
+
+    Ok(())
+}
+
+// converts a value to the type of a field of a struct or variant, if the field has one
+fn field_value(
+    definition: &Element,
+    name: &str,
+    field: &str,
+    value: Value,
+) -> Result<Value, RuntimeError> {
+    let field_element = definition
+        .children
+        .iter()
+        .find(|child| child.attributes.get("name").is_some_and(|n| n == field))
+        .ok_or_else(|| RuntimeError::UndefinedField {
+            name: name.to_string(),
+            field: field.to_string(),
+        })?;
+
+    let Some(expected) = field_element.attributes.get("type") else {
+        return Ok(value);
+    };
+
+    // fields of a struct or enum type don't convert anything, they only accept values of that type
+    let converted = match Value::of_type(expected) {
+        Some(target) => value.convert_to(&target),
+        None => (value.type_name() == *expected).then(|| value.clone()),
+    };
+
+    converted.ok_or_else(|| RuntimeError::InvalidFieldType {
+        name: name.to_string(),
+        field: field.to_string(),
+        expected: expected.clone(),
+        value,
+    })
+}
+
+// adds a value to the children of a node built by <node>: nodes as they are, lists item by item, and anything else as text
+fn push_node_child(children: &mut Vec<Element>, value: Value, span: SourceSpan) {
+    match value {
+        Value::Null => {}
+        Value::Node(node) => children.push(*node),
+        Value::List(items) => {
+            for item in items {
+                push_node_child(children, item, span);
+            }
+        }
+        value => children.push(Element {
+            name: String::new(),
+            prefix: None,
+            attributes: HashMap::from([("_text".to_string(), value.to_string())]),
+            children: Vec::new(),
+            span,
+            expansions: Vec::new(),
+        }),
+    }
+}
+
+// calls a function with the attributes and children of the element, apart from the attribute naming the function
+fn call_function(
+    name: &str,
+    element: &Element,
+    name_attribute: Option<&str>,
+    depth: u32,
+    variables: &mut Variables,
+    specials: &Specials,
+    state: &mut State,
+) -> Result<Value> {
+    let func = state
+        .functions
+        .get(name)
+        .cloned()
+        .ok_or_else(|| RuntimeError::UndefinedFunction(name.to_string()))?;
+
+    let children = element
+        .children
+        .iter()
+        .map(|child| interpret(child, depth + 1, variables, specials, state))
+        .collect::<Result<Vec<Value>>>()?;
+
+    let child_count = children.len();
+
+    let mut attributes = element
+        .attributes
+        .iter()
+        .filter(|(k, _)| Some(k.as_str()) != name_attribute)
+        .map(|(k, v)| (k.clone(), Value::from(v.as_str())))
+        .collect::<Vec<_>>();
+    attributes.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let frame = CallFrame {
+        name: name.to_string(),
+        span: element.span,
+        attributes,
+        children: children.clone(),
+    };
+
+    // the attributes are collected last, so they hide the children specials with the same name
+    let scope = children
+        .into_iter()
+        .enumerate()
+        .map(|(i, value)| (format!("child:{i}"), value))
+        .chain(std::iter::once((
+            String::from("child_count"),
+            Value::Int(child_count as i64),
+        )))
+        .chain(
+            element
+                .attributes
+                .iter()
+                .map(|(k, v)| (k.clone(), Value::from(v.as_str()))),
+        )
+        .collect::<HashMap<_, _>>();
+    let specials = specials.with(&scope);
+
+    // constants defined inside the function go away with its variables
+    let mut variables = variables.for_call();
+    // the loops the function is called from can't be stopped from inside it
+    let loops = std::mem::take(&mut state.loops);
+
+    if let Err(err) = state.enter_call(frame) {
+        state.record_trace();
+        return Err(err.into());
+    }
+
+    let ret = func.into_iter().try_fold(Value::Null, |_, child| {
+        interpret(&child, depth + 1, &mut variables, &specials, state)
+    });
+
+    let ret = match ret {
+        Ok(val) => Ok(val),
+        Err(err) => match err.downcast::<BlockControl>() {
+            Ok(BlockControl::Break(val) | BlockControl::BreakLoop(val)) => Ok(val),
+            // a <continue /> that didn't find a loop in the function doesn't skip an iteration of the caller's loop
+            Ok(BlockControl::Continue) => {
+                state.record_trace();
+                Err(RuntimeError::ContinueOutsideLoop.into())
+            }
+            Err(err) => {
+                state.record_trace();
+                Err(err)
+            }
+        },
+    };
+
+    state.exit_call();
+    state.loops = loops;
+
+    ret
+}
+
+fn evaluate(
+    element: &Element,
+    depth: u32,
+    variables: &mut Variables,
+    specials: &Specials,
+    state: &mut State,
 ) -> Result<Value> {
     Ok(match element.name.to_lowercase().as_str() {
         "program" if depth == 0 => {
             match element.children.iter().try_fold(Value::Null, |_, child| {
-                interpret(child, depth + 1, variables, specials, functions)
+                interpret(child, depth + 1, variables, specials, state)
             }) {
                 Ok(val) => val,
                 Err(err) => match err.downcast::<BlockControl>() {
-                    Ok(BlockControl::Break(val)) => val,
-                    Ok(BlockControl::Continue) => return Err(BlockControl::Continue.into()),
+                    Ok(BlockControl::Break(val) | BlockControl::BreakLoop(val)) => val,
+                    Ok(BlockControl::Continue) => bail!(RuntimeError::ContinueOutsideLoop),
                     Err(err) => return Err(err),
                 },
             }
         }
-        _ if depth == 0 => bail!("Root element must be <program>"),
+        _ if depth == 0 => bail!(RuntimeError::InvalidRoot),
+
+        // prefixed elements skip the built-in elements, so they keep calling the function if one with the same name is added
+        _ if element.prefix.as_ref() == Some(&state.function_prefix) => call_function(
+            &element.name,
+            element,
+            None,
+            depth,
+            variables,
+            specials,
+            state,
+        )?,
 
         "space" => {
             let count = element
@@ -58,6 +611,8 @@ pub fn interpret(
                 .and_then(|s| s.parse::<usize>().ok())
                 .unwrap_or(1);
 
+            state.check_string_length(count)?;
+
             let spaces = " ".repeat(count);
 
             Value::Str(spaces)
@@ -67,7 +622,7 @@ pub fn interpret(
             let text = element.children.iter().try_fold(
                 element.attributes.get("_text").cloned().unwrap_or_default(),
                 |value, child| {
-                    let child_value = interpret(child, depth + 1, variables, specials, functions)?;
+                    let child_value = interpret(child, depth + 1, variables, specials, state)?;
 
                     Ok::<_, Report>(if child_value.is_null() {
                         value
@@ -79,46 +634,107 @@ pub fn interpret(
             Value::Str(text)
         }
 
+        "format" => {
+            // text children make up the template, the other children fill the positional placeholders
+            let mut template = String::new();
+            let mut arguments = Vec::new();
+
+            for child in &element.children {
+                if child.name.is_empty() {
+                    template.push_str(child.attributes.get("_text").map_or("", String::as_str));
+                } else {
+                    arguments.push(interpret(child, depth + 1, variables, specials, state)?);
+                }
+            }
+
+            interpolate(&template, state, |name| match name.parse::<usize>() {
+                Ok(index) => arguments.get(index).cloned(),
+                Err(_) => variables
+                    .get(name)
+                    .cloned()
+                    .or_else(|| specials.get(name).cloned()),
+            })?
+            .into()
+        }
+
         "null" => Value::Null,
 
-        name @ ("int" | "integer") => {
+        "bigint" => {
+            ensure!(
+                element.children.len() == 1,
+                RuntimeError::invalid_children("bigint", "exactly one child")
+            );
+
+            let child = &element.children[0];
+            let value = interpret(child, depth + 1, variables, specials, state)?;
+
+            match &value {
+                Value::Str(text) if element.attributes.contains_key("base") => {
+                    parse_radix(element, text)?
+                }
+                value => value.as_bigint(),
+            }
+            .ok_or_else(|| RuntimeError::conversion_failed(&value, "a big integer"))?
+            .into()
+        }
+
+        "decimal" => {
             ensure!(
                 element.children.len() == 1,
-                "Expected exactly one child in <{name}> element"
+                RuntimeError::invalid_children("decimal", "exactly one child")
             );
 
             let child = &element.children[0];
-            let value = interpret(child, depth + 1, variables, specials, functions)?;
+            let value = interpret(child, depth + 1, variables, specials, state)?;
 
             value
-                .as_int()
-                .wrap_err("Failed to convert value to an integer")?
+                .as_decimal()
+                .ok_or_else(|| RuntimeError::conversion_failed(&value, "a decimal"))?
                 .into()
         }
 
+        name @ ("int" | "integer") => {
+            ensure!(
+                element.children.len() == 1,
+                RuntimeError::invalid_children(name, "exactly one child")
+            );
+
+            let child = &element.children[0];
+            let value = interpret(child, depth + 1, variables, specials, state)?;
+
+            match &value {
+                Value::Str(text) if element.attributes.contains_key("base") => {
+                    parse_radix(element, text)?.and_then(|value| value.to_i64())
+                }
+                value => value.as_int(),
+            }
+            .ok_or_else(|| RuntimeError::conversion_failed(&value, "an integer"))?
+            .into()
+        }
+
         "float" => {
             ensure!(
                 element.children.len() == 1,
-                "Expected exactly one child in <float> element"
+                RuntimeError::invalid_children("float", "exactly one child")
             );
 
             let child = &element.children[0];
-            let value = interpret(child, depth + 1, variables, specials, functions)?;
+            let value = interpret(child, depth + 1, variables, specials, state)?;
 
             value
                 .as_float()
-                .wrap_err("Failed to convert value to a float")?
+                .ok_or_else(|| RuntimeError::conversion_failed(&value, "a float"))?
                 .into()
         }
 
         "bool" => {
             ensure!(
                 element.children.len() == 1,
-                "Expected exactly one child in <bool> element"
+                RuntimeError::invalid_children("bool", "exactly one child")
             );
 
             let child = &element.children[0];
-            let value = interpret(child, depth + 1, variables, specials, functions)?;
+            let value = interpret(child, depth + 1, variables, specials, state)?;
 
             value.as_bool().into()
         }
@@ -126,11 +742,19 @@ pub fn interpret(
         "true" => Value::Bool(true),
         "false" => Value::Bool(false),
 
+        "list" => Value::List(
+            element
+                .children
+                .iter()
+                .map(|child| interpret(child, depth + 1, variables, specials, state))
+                .collect::<Result<Vec<Value>>>()?,
+        ),
+
         "type" => {
             let values = element
                 .children
                 .iter()
-                .map(|child| interpret(child, depth + 1, variables, specials, functions))
+                .map(|child| interpret(child, depth + 1, variables, specials, state))
                 .collect::<Result<Vec<Value>>>()?;
 
             let types = values
@@ -155,18 +779,24 @@ pub fn interpret(
             } else {
                 ensure!(
                     element.children.len() == 1,
-                    "Expected exactly one child or the `duration` attribute in <{name}> element"
+                    RuntimeError::invalid_children(
+                        name,
+                        "exactly one child or the `duration` attribute"
+                    )
                 );
 
                 let child = &element.children[0];
-                let value = interpret(child, depth + 1, variables, specials, functions)?;
+                let value = interpret(child, depth + 1, variables, specials, state)?;
 
                 value
                     .as_int()
-                    .wrap_err("Failed to convert value to an integer")? as u64
+                    .and_then(|seed| u64::try_from(seed).ok())
+                    .ok_or_else(|| {
+                        RuntimeError::conversion_failed(&value, "a non-negative integer")
+                    })?
             };
 
-            sleep(Duration::from_millis(duration));
+            state.sleep(Duration::from_millis(duration))?;
 
             Value::Null
         }
@@ -180,7 +810,7 @@ pub fn interpret(
 
             let mut output = String::new();
             for child in &element.children {
-                let value = interpret(child, depth + 1, variables, specials, functions)?;
+                let value = interpret(child, depth + 1, variables, specials, state)?;
                 output.push_str(&value.to_string());
             }
 
@@ -188,7 +818,7 @@ pub fn interpret(
                 println!("{output}");
             } else {
                 print!("{output}");
-                io::stdout().flush().into_diagnostic()?;
+                io::stdout().flush().map_err(RuntimeError::from)?;
             }
 
             output.into()
@@ -196,7 +826,9 @@ pub fn interpret(
 
         "readline" => {
             let mut input = String::new();
-            io::stdin().read_line(&mut input).into_diagnostic()?;
+            io::stdin()
+                .read_line(&mut input)
+                .map_err(RuntimeError::from)?;
 
             Value::Str(input.trim_end_matches(['\r', '\n']).to_string())
         }
@@ -216,11 +848,11 @@ pub fn interpret(
 
             ensure!(
                 element.children.len() == 1,
-                "Expected exactly one child in <trim> element"
+                RuntimeError::invalid_children("trim", "exactly one child")
             );
 
             let child = &element.children[0];
-            let value = interpret(child, depth + 1, variables, specials, functions)?;
+            let value = interpret(child, depth + 1, variables, specials, state)?;
 
             let value = value.to_string();
 
@@ -250,7 +882,7 @@ pub fn interpret(
             let values = element
                 .children
                 .iter()
-                .map(|child| interpret(child, depth + 1, variables, specials, functions))
+                .map(|child| interpret(child, depth + 1, variables, specials, state))
                 .collect::<Result<Vec<Value>>>()?;
 
             let joined = values
@@ -265,11 +897,11 @@ pub fn interpret(
         name @ ("unwrap" | "expect") => {
             ensure!(
                 element.children.len() == 1,
-                "Expected exactly one child in <{name}> element"
+                RuntimeError::invalid_children(name, "exactly one child")
             );
 
             let child = &element.children[0];
-            let value = interpret(child, depth + 1, variables, specials, functions)?;
+            let value = interpret(child, depth + 1, variables, specials, state)?;
 
             if value.is_null() {
                 let msg = element
@@ -278,58 +910,130 @@ pub fn interpret(
                     .cloned()
                     .unwrap_or_else(|| "Unwrapped value is null".to_string());
 
-                bail!(msg);
+                bail!(RuntimeError::NullUnwrapped(msg));
             }
 
             value
         }
 
         "throw" => {
-            if let Some(msg) = element.attributes.get("message").cloned() {
-                bail!(msg);
+            let kind = element
+                .attributes
+                .get("kind")
+                .or_else(|| element.attributes.get("code"))
+                .cloned()
+                .unwrap_or_else(|| "Error".to_string());
+
+            let data = match element
+                .children
+                .iter()
+                .find(|child| child.name.to_lowercase() == "data")
+            {
+                Some(data) => {
+                    ensure!(
+                        data.children.len() == 1,
+                        RuntimeError::invalid_children("data", "exactly one child")
+                    );
+
+                    interpret(&data.children[0], depth + 2, variables, specials, state)?
+                }
+                None => Value::Null,
+            };
+
+            let message = if let Some(msg) = element.attributes.get("message").cloned() {
+                msg
             } else {
-                let text = element.children.iter().try_fold(
-                    element.attributes.get("_text").cloned().unwrap_or_default(),
-                    |value, child| {
-                        let child_value =
-                            interpret(child, depth + 1, variables, specials, functions)?;
-
-                        Ok::<_, Report>(if child_value.is_null() {
-                            value
-                        } else {
-                            format!("{value}{child_value}")
-                        })
-                    },
-                )?;
+                let text = element
+                    .children
+                    .iter()
+                    .filter(|child| child.name.to_lowercase() != "data")
+                    .try_fold(
+                        element.attributes.get("_text").cloned().unwrap_or_default(),
+                        |value, child| {
+                            let child_value =
+                                interpret(child, depth + 1, variables, specials, state)?;
+
+                            Ok::<_, Report>(if child_value.is_null() {
+                                value
+                            } else {
+                                format!("{value}{child_value}")
+                            })
+                        },
+                    )?;
 
                 if text.is_empty() {
-                    bail!("An error occurred, but no message was provided.");
+                    "An error occurred, but no message was provided.".to_string()
                 } else {
-                    bail!(text);
+                    text
                 }
+            };
+
+            return Err(RuntimeError::Thrown {
+                message,
+                kind,
+                data,
+            }
+            .into());
+        }
+
+        "rethrow" => {
+            ensure!(
+                element.children.is_empty(),
+                RuntimeError::invalid_children("rethrow", "no children")
+            );
+
+            let caught = state
+                .caught
+                .last_mut()
+                .ok_or(RuntimeError::RethrowOutsideCatch)?;
+
+            // the same error, so it keeps its kind, code, labels and stack trace
+            if let Some((err, trace)) = caught.take() {
+                state.trace = Some(trace);
+                return Err(err);
+            }
+
+            // the error was already rethrown and caught again inside this <catch>, so only its specials are left
+            let special = |name: &str| specials.get(name).cloned();
+
+            let message = special("error").ok_or(RuntimeError::RethrowOutsideCatch)?;
+
+            return Err(RuntimeError::Thrown {
+                message: message.to_string(),
+                kind: special("error_kind")
+                    .map(|kind| kind.to_string())
+                    .unwrap_or_else(|| "Error".to_string()),
+                data: special("error_data").unwrap_or_default(),
             }
+            .into());
         }
 
         name @ ("return" | "break") => {
             ensure!(
                 element.children.len() <= 1,
-                "Expected at most one child in <{name}> element"
+                RuntimeError::invalid_children(name, "at most one child")
             );
 
             let value = if element.children.is_empty() {
                 Value::Null
             } else {
                 let child = &element.children[0];
-                interpret(child, depth + 1, variables, specials, functions)?
+                interpret(child, depth + 1, variables, specials, state)?
             };
 
+            // <return> leaves the nearest block, while <break> leaves the nearest loop, or the nearest block like
+            // <return> when it isn't in a loop
+            if name == "break" && state.loops > 0 {
+                return Err(BlockControl::BreakLoop(value).into());
+            }
+
             return Err(BlockControl::Break(value).into());
         }
 
         name @ ("continue" | "next") => {
             ensure!(
                 element.children.is_empty(),
-                "Expected no children in <{name}> element"
+                RuntimeError::invalid_children(name, "no children")
             );
 
             return Err(BlockControl::Continue.into());
@@ -338,7 +1042,7 @@ pub fn interpret(
         "exit" => {
             ensure!(
                 element.children.is_empty(),
-                "Expected no children in <exit> element"
+                RuntimeError::invalid_children("exit", "no children")
             );
 
             let code = element
@@ -359,20 +1063,26 @@ pub fn interpret(
                 } else {
                     ensure!(
                         element.children.len() == 1,
-                        "Expected exactly one child or the `var` attribute in <get> element"
+                        RuntimeError::invalid_children(
+                            "get",
+                            "exactly one child or the `var` attribute"
+                        )
                     );
 
                     let child = &element.children[0];
-                    interpret(child, depth + 1, variables, specials, functions)?
+                    interpret(child, depth + 1, variables, specials, state)?
                 }
             } else {
                 ensure!(
                     element.children.len() == 1,
-                    "Expected exactly one child or the `var` attribute in <get> element"
+                    RuntimeError::invalid_children(
+                        "get",
+                        "exactly one child or the `var` attribute"
+                    )
                 );
 
                 let child = &element.children[0];
-                let value = interpret(child, depth + 1, variables, specials, functions)?;
+                let value = interpret(child, depth + 1, variables, specials, state)?;
 
                 variables
                     .get(&value.to_string())
@@ -385,182 +1095,650 @@ pub fn interpret(
             let name = element
                 .attributes
                 .get("var")
-                .wrap_err("Expected the `var` attribute in <set> element")?
+                .ok_or_else(|| RuntimeError::missing_attribute("set", "var"))?
                 .clone();
 
             ensure!(
                 element.children.len() == 1,
-                "Expected exactly one child in <set> element"
+                RuntimeError::invalid_children("set", "exactly one child")
             );
 
             let child = &element.children[0];
-            let value = interpret(child, depth + 1, variables, specials, functions)?;
+            let value = interpret(child, depth + 1, variables, specials, state)?;
 
-            variables.insert(name, value.clone());
+            variables.set(&name, value.clone())?;
 
             value
         }
 
-        "special" => {
-            if let Some(name) = element.attributes.get("name") {
-                specials
-                    .iter()
-                    .find_map(|specials_map| specials_map.get(name).cloned())
-                    .wrap_err(format!("Special `{name}` not found"))?
-            } else {
-                ensure!(
-                    element.children.len() == 1,
-                    "Expected exactly one child or the `name` attribute in <special> element"
-                );
+        name @ ("inc" | "dec") => {
+            let var = element
+                .attributes
+                .get("var")
+                .ok_or_else(|| RuntimeError::missing_attribute(name, "var"))?;
+
+            let amount = match (element.attributes.get("by"), element.children.as_slice()) {
+                (None, []) => Value::Int(1),
+                (Some(by), []) => by
+                    .parse::<i64>()
+                    .map(Value::Int)
+                    .or_else(|_| by.parse::<f64>().map(Value::Float))
+                    .map_err(|_| RuntimeError::InvalidAttribute {
+                        element: name.to_string(),
+                        attribute: "by".to_string(),
+                        value: by.clone(),
+                    })?,
+                (None, [child]) => interpret(child, depth + 1, variables, specials, state)?,
+                _ => bail!(RuntimeError::invalid_children(
+                    name,
+                    "either a `by` attribute or one child"
+                )),
+            };
 
-                let child = &element.children[0];
-                let value = interpret(child, depth + 1, variables, specials, functions)?;
+            let operation = if name == "inc" { "add" } else { "sub" };
+            let overflow = overflow_mode(element, state)?;
 
-                let name = value.to_string();
+            update_variable(variables, state, var, operation, amount, overflow)?;
 
-                specials
-                    .iter()
-                    .find_map(|specials_map| specials_map.get(&name).cloned())
-                    .wrap_err(format!("Special `{name}` not found"))?
-            }
+            // unlike <update>, the result is the new value, which for numbers is cheap to copy
+            variables.get(var).cloned().unwrap_or_default()
         }
 
-        "add" | "sum" => element
-            .children
-            .iter()
-            .map(|child| interpret(child, depth + 1, variables, specials, functions))
-            .sum::<Result<Value>>()?,
+        "update" => {
+            let var = element
+                .attributes
+                .get("var")
+                .ok_or_else(|| RuntimeError::missing_attribute("update", "var"))?;
+
+            let operation = element
+                .attributes
+                .get("op")
+                .ok_or_else(|| RuntimeError::missing_attribute("update", "op"))?;
+
+            ensure!(
+                matches!(
+                    operation.as_str(),
+                    "add" | "sub" | "mul" | "div" | "mod" | "concat"
+                ),
+                RuntimeError::InvalidAttribute {
+                    element: "update".to_string(),
+                    attribute: "op".to_string(),
+                    value: operation.clone(),
+                }
+            );
 
-        name @ ("neg" | "negate" | "negative") => {
             ensure!(
                 element.children.len() == 1,
-                "Expected exactly one child in <{name}> element"
+                RuntimeError::invalid_children("update", "exactly one child")
             );
 
-            let child = &element.children[0];
-            let value = interpret(child, depth + 1, variables, specials, functions)?;
+            let operand = interpret(&element.children[0], depth + 1, variables, specials, state)?;
+            let overflow = overflow_mode(element, state)?;
 
-            (-value)?
+            // the new value isn't returned, so a long string or list that's appended to isn't copied
+            update_variable(variables, state, var, operation, operand, overflow)?;
+
+            Value::Null
         }
 
-        "not" => {
+        "const" => {
+            let name = element
+                .attributes
+                .get("var")
+                .ok_or_else(|| RuntimeError::missing_attribute("const", "var"))?
+                .clone();
+
             ensure!(
                 element.children.len() == 1,
-                "Expected exactly one child in <not> element"
+                RuntimeError::invalid_children("const", "exactly one child")
             );
 
             let child = &element.children[0];
-            let value = interpret(child, depth + 1, variables, specials, functions)?;
+            let value = interpret(child, depth + 1, variables, specials, state)?;
 
-            !value
+            variables.set_constant(&name, value.clone())?;
+
+            value
         }
 
-        "and" => {
+        "freeze" => {
+            let name = element
+                .attributes
+                .get("var")
+                .ok_or_else(|| RuntimeError::missing_attribute("freeze", "var"))?;
+
             ensure!(
-                element.children.len() >= 2,
-                "Expected at least 2 children in <and> element"
+                element.children.is_empty(),
+                RuntimeError::invalid_children("freeze", "no children")
             );
 
-            let values = element
-                .children
-                .iter()
-                .map(|child| interpret(child, depth + 1, variables, specials, functions))
-                .collect::<Result<Vec<Value>>>()?;
+            variables.freeze(name)?.clone()
+        }
+
+        "special" => {
+            if let Some(name) = element.attributes.get("name") {
+                specials
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| RuntimeError::UndefinedSpecial(name.clone()))?
+            } else {
+                ensure!(
+                    element.children.len() == 1,
+                    RuntimeError::invalid_children(
+                        "special",
+                        "exactly one child or the `name` attribute"
+                    )
+                );
+
+                let child = &element.children[0];
+                let value = interpret(child, depth + 1, variables, specials, state)?;
+
+                let name = value.to_string();
 
-            let all_true = values.iter().all(|value| value.as_bool());
-            Value::Bool(all_true)
+                specials
+                    .get(&name)
+                    .cloned()
+                    .ok_or_else(|| RuntimeError::UndefinedSpecial(name.clone()))?
+            }
         }
 
-        "or" => {
-            ensure!(
-                element.children.len() >= 2,
-                "Expected at least 2 children in <or> element"
-            );
+        "add" | "sum" => {
+            let overflow = overflow_mode(element, state)?;
 
             let values = element
                 .children
                 .iter()
-                .map(|child| interpret(child, depth + 1, variables, specials, functions))
+                .map(|child| interpret(child, depth + 1, variables, specials, state))
                 .collect::<Result<Vec<Value>>>()?;
 
-            let any_true = values.iter().any(|value| value.as_bool());
-            Value::Bool(any_true)
+            values
+                .into_iter()
+                .try_fold(Value::Null, |acc, value| acc.add(value, overflow))?
         }
 
-        name @ ("abs" | "absolute") => {
+        name @ ("neg" | "negate" | "negative") => {
             ensure!(
                 element.children.len() == 1,
-                "Expected exactly one child in <{name}> element"
+                RuntimeError::invalid_children(name, "exactly one child")
             );
 
             let child = &element.children[0];
-            let value = interpret(child, depth + 1, variables, specials, functions)?;
+            let value = interpret(child, depth + 1, variables, specials, state)?;
 
-            value.abs()?
+            value.neg(overflow_mode(element, state)?)?
+        }
+
+        "not" => {
+            ensure!(
+                element.children.len() == 1,
+                RuntimeError::invalid_children("not", "exactly one child")
+            );
+
+            let child = &element.children[0];
+            let value = interpret(child, depth + 1, variables, specials, state)?;
+
+            !value
+        }
+
+        name @ ("and" | "or") => {
+            ensure!(
+                element.children.len() >= 2,
+                RuntimeError::invalid_children(name, "at least 2 children")
+            );
+
+            let operand = element
+                .attributes
+                .get("operand")
+                .is_some_and(|s| Value::from(s.as_str()).as_bool());
+
+            // stops at the first falsy child in <and> or the first truthy one in <or>, which decides the result
+            let mut deciding = Value::Null;
+            for child in &element.children {
+                deciding = interpret(child, depth + 1, variables, specials, state)?;
+
+                if deciding.as_bool() == (name == "or") {
+                    break;
+                }
+            }
+
+            if operand {
+                deciding
+            } else {
+                Value::Bool(deciding.as_bool())
+            }
+        }
+
+        name @ ("abs" | "absolute") => {
+            ensure!(
+                element.children.len() == 1,
+                RuntimeError::invalid_children(name, "exactly one child")
+            );
+
+            let child = &element.children[0];
+            let value = interpret(child, depth + 1, variables, specials, state)?;
+
+            value.abs(overflow_mode(element, state)?)?
         }
 
         "sub" | "subtract" | "difference" => {
             let values = element
                 .children
                 .iter()
-                .map(|child| interpret(child, depth + 1, variables, specials, functions))
+                .map(|child| interpret(child, depth + 1, variables, specials, state))
                 .collect::<Result<Vec<Value>>>()?;
 
             let mut values = values.into_iter();
 
+            let overflow = overflow_mode(element, state)?;
+
             let first = values.next().unwrap_or_default();
-            values.try_fold(first, |acc, value| acc - value)?
+            values.try_fold(first, |acc, value| acc.sub(value, overflow))?
         }
 
         "mul" | "multiply" | "product" => {
             let values = element
                 .children
                 .iter()
-                .map(|child| interpret(child, depth + 1, variables, specials, functions))
+                .map(|child| interpret(child, depth + 1, variables, specials, state))
                 .collect::<Result<Vec<Value>>>()?;
 
             let mut values = values.into_iter();
 
+            let overflow = overflow_mode(element, state)?;
+
             let first = values.next().unwrap_or_default();
-            values.try_fold(first, |acc, value| acc * value)?
+            values.try_fold(first, |acc, value| {
+                if let Some(length) = repeated_length(&acc, &value) {
+                    state.check_string_length(length)?;
+                }
+
+                Ok::<_, Report>(acc.mul(value, overflow)?)
+            })?
         }
 
         "div" | "divide" | "quotient" => {
             let values = element
                 .children
                 .iter()
-                .map(|child| interpret(child, depth + 1, variables, specials, functions))
+                .map(|child| interpret(child, depth + 1, variables, specials, state))
                 .collect::<Result<Vec<Value>>>()?;
 
             let mut values = values.into_iter();
 
+            let overflow = overflow_mode(element, state)?;
+
             let first = values.next().unwrap_or_default();
-            values.try_fold(first, |acc, value| acc / value)?
+            values.try_fold(first, |acc, value| acc.div(value, overflow))?
         }
 
         "mod" | "modulo" | "remainder" => {
             let values = element
                 .children
                 .iter()
-                .map(|child| interpret(child, depth + 1, variables, specials, functions))
+                .map(|child| interpret(child, depth + 1, variables, specials, state))
+                .collect::<Result<Vec<Value>>>()?;
+
+            let mut values = values.into_iter();
+
+            let first = values.next().unwrap_or_default();
+            values.try_fold(first, |acc, value| acc.rem(value))?
+        }
+
+        name @ ("bit-and" | "bit-or" | "bit-xor") => {
+            let values = element
+                .children
+                .iter()
+                .map(|child| interpret(child, depth + 1, variables, specials, state))
                 .collect::<Result<Vec<Value>>>()?;
 
             let mut values = values.into_iter();
 
             let first = values.next().unwrap_or_default();
-            values.try_fold(first, |acc, value| acc % value)?
+            values.try_fold(first, |acc, value| match name {
+                "bit-and" => acc.bit_and(value),
+                "bit-or" => acc.bit_or(value),
+                _ => acc.bit_xor(value),
+            })?
+        }
+
+        "bit-not" => {
+            let (value, _) = operands(element, "bit-not", &[], depth, variables, specials, state)?;
+
+            value.bit_not()?
+        }
+
+        name @ ("shl" | "shr") => {
+            let (value, arguments) =
+                operands(element, name, &["by"], depth, variables, specials, state)?;
+            let [amount] = <[_; 1]>::try_from(arguments).unwrap_or_default();
+
+            let amount =
+                int_operand(amount)?.ok_or_else(|| RuntimeError::missing_attribute(name, "by"))?;
+
+            value.shift_left(if name == "shl" {
+                amount
+            } else {
+                amount.saturating_neg()
+            })?
+        }
+
+        "to-string" => {
+            let (value, _) =
+                operands(element, "to-string", &[], depth, variables, specials, state)?;
+
+            match base_attribute(element)? {
+                None => Value::Str(value.to_string()),
+                Some(base) => {
+                    // strings are parsed as base 10 integers, like <int> does
+                    let number = match &value {
+                        Value::Int(_) | Value::BigInt(_) | Value::Bool(_) | Value::Str(_) => {
+                            value.as_bigint()
+                        }
+                        _ => None,
+                    }
+                    .ok_or_else(|| RuntimeError::conversion_failed(&value, "an integer"))?;
+
+                    let prefix = element
+                        .attributes
+                        .get("prefix")
+                        .is_some_and(|s| Value::from(s.as_str()).as_bool());
+                    let uppercase = element
+                        .attributes
+                        .get("uppercase")
+                        .is_some_and(|s| Value::from(s.as_str()).as_bool());
+
+                    let digits = number.magnitude().to_str_radix(base);
+
+                    Value::Str(format!(
+                        "{}{}{}",
+                        if number.is_negative() { "-" } else { "" },
+                        match (prefix, base) {
+                            (true, 2) => "0b",
+                            (true, 8) => "0o",
+                            (true, 16) => "0x",
+                            _ => "",
+                        },
+                        if uppercase {
+                            digits.to_uppercase()
+                        } else {
+                            digits
+                        }
+                    ))
+                }
+            }
+        }
+
+        "pow" | "power" => {
+            ensure!(
+                element.children.len() == 2,
+                RuntimeError::invalid_children("pow", "exactly 2 children")
+            );
+
+            let values = element
+                .children
+                .iter()
+                .map(|child| interpret(child, depth + 1, variables, specials, state))
+                .collect::<Result<Vec<Value>>>()?;
+
+            let overflow = overflow_mode(element, state)?;
+
+            match (&values[0], &values[1]) {
+                (Value::Int(base), Value::Int(exponent))
+                    if let Ok(exponent) = u32::try_from(*exponent) =>
+                {
+                    if overflow == Overflow::Promote && base.checked_pow(exponent).is_none() {
+                        state
+                            .check_bigint_bits(BigInt::from(*base).bits() * u64::from(exponent))?;
+                    }
+
+                    overflow.apply(
+                        "exponentiate",
+                        base.checked_pow(exponent),
+                        || base.wrapping_pow(exponent),
+                        || base.saturating_pow(exponent),
+                        || BigInt::from(*base).pow(exponent),
+                    )?
+                }
+                (Value::BigInt(base), Value::Int(exponent))
+                    if let Ok(exponent) = u32::try_from(*exponent) =>
+                {
+                    state.check_bigint_bits(base.bits().saturating_mul(u64::from(exponent)))?;
+                    Value::BigInt(base.pow(exponent))
+                }
+                (Value::Decimal(base), Value::Int(exponent)) => Value::Decimal(
+                    base.checked_powi(*exponent)
+                        .ok_or_else(|| RuntimeError::DecimalOverflow("exponentiate".to_string()))?,
+                ),
+                (base, exponent) => {
+                    Value::Float(float_operand(base)?.powf(float_operand(exponent)?))
+                }
+            }
+        }
+
+        name @ ("sqrt" | "cbrt" | "exp" | "sin" | "cos" | "tan" | "asin" | "acos" | "atan"
+        | "sinh" | "cosh" | "tanh") => {
+            let (value, _) = operands(element, name, &[], depth, variables, specials, state)?;
+            let x = float_operand(&value)?;
+
+            Value::Float(match name {
+                "sqrt" => x.sqrt(),
+                "cbrt" => x.cbrt(),
+                "exp" => x.exp(),
+                "sin" => x.sin(),
+                "cos" => x.cos(),
+                "tan" => x.tan(),
+                "asin" => x.asin(),
+                "acos" => x.acos(),
+                "atan" => x.atan(),
+                "sinh" => x.sinh(),
+                "cosh" => x.cosh(),
+                _ => x.tanh(),
+            })
+        }
+
+        "atan2" => {
+            ensure!(
+                element.children.len() == 2,
+                RuntimeError::invalid_children("atan2", "exactly 2 children")
+            );
+
+            let values = element
+                .children
+                .iter()
+                .map(|child| interpret(child, depth + 1, variables, specials, state))
+                .collect::<Result<Vec<Value>>>()?;
+
+            Value::Float(float_operand(&values[0])?.atan2(float_operand(&values[1])?))
+        }
+
+        "log" => {
+            let (value, arguments) =
+                operands(element, "log", &["base"], depth, variables, specials, state)?;
+            let [base] = <[_; 1]>::try_from(arguments).unwrap_or_default();
+
+            let x = float_operand(&value)?;
+
+            Value::Float(match base {
+                Some(base) => x.log(float_operand(&base)?),
+                None => x.ln(),
+            })
+        }
+
+        name @ ("floor" | "ceil") => {
+            let (value, _) = operands(element, name, &[], depth, variables, specials, state)?;
+
+            match value {
+                value @ (Value::Int(_) | Value::BigInt(_)) => value,
+                Value::Decimal(d) => {
+                    Value::Decimal(if name == "floor" { d.floor() } else { d.ceil() })
+                }
+                value => {
+                    let x = float_operand(&value)?;
+                    Value::Float(if name == "floor" { x.floor() } else { x.ceil() })
+                }
+            }
+        }
+
+        "round" => {
+            let (value, arguments) = operands(
+                element,
+                "round",
+                &["digits"],
+                depth,
+                variables,
+                specials,
+                state,
+            )?;
+            let [digits] = <[_; 1]>::try_from(arguments).unwrap_or_default();
+
+            let digits = int_operand(digits)?.unwrap_or(0).clamp(-308, 308) as i32;
+            let scale = 10f64.powi(digits.abs());
+
+            match value {
+                value @ (Value::Int(_) | Value::BigInt(_)) if digits >= 0 => value,
+                Value::Int(i) => {
+                    // rounding to more digits than an int has always gives 0, which the saturated scale does too
+                    let scale = 10i128
+                        .checked_pow(digits.unsigned_abs())
+                        .unwrap_or(i128::MAX);
+                    let magnitude = (i128::from(i).abs() + scale / 2) / scale * scale;
+                    let rounded = if i < 0 { -magnitude } else { magnitude };
+
+                    overflow_mode(element, state)?.apply(
+                        "round",
+                        i64::try_from(rounded).ok(),
+                        || rounded as i64,
+                        || rounded.clamp(i64::MIN.into(), i64::MAX.into()) as i64,
+                        || BigInt::from(rounded),
+                    )?
+                }
+                Value::BigInt(i) => {
+                    let scale = BigInt::from(10).pow(digits.unsigned_abs());
+                    let rounded: BigInt = (i.abs() + &scale / 2) / &scale * &scale;
+
+                    Value::BigInt(if i.is_negative() { -rounded } else { rounded })
+                }
+                Value::Decimal(d) if digits >= 0 => Value::Decimal(
+                    d.round_dp_with_strategy(digits as u32, RoundingStrategy::MidpointAwayFromZero),
+                ),
+                Value::Decimal(d) => {
+                    // decimals can't hold more than 28 digits, so rounding to more than that always gives 0
+                    let scale =
+                        Decimal::from_i128_with_scale(10i128.pow(digits.unsigned_abs().min(28)), 0);
+
+                    Value::Decimal(
+                        (d / scale)
+                            .round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero)
+                            .checked_mul(scale)
+                            .ok_or_else(|| RuntimeError::DecimalOverflow("round".to_string()))?,
+                    )
+                }
+                value => {
+                    let x = float_operand(&value)?;
+                    Value::Float(if digits >= 0 {
+                        (x * scale).round() / scale
+                    } else {
+                        (x / scale).round() * scale
+                    })
+                }
+            }
+        }
+
+        name @ ("min" | "max") => {
+            let values = element
+                .children
+                .iter()
+                .map(|child| interpret(child, depth + 1, variables, specials, state))
+                .collect::<Result<Vec<Value>>>()?;
+
+            // a single list is treated as the values to compare
+            let values = match <[Value; 1]>::try_from(values) {
+                Ok([Value::List(items)]) => items,
+                Ok([value]) => vec![value],
+                Err(values) => values,
+            };
+
+            let mut values = values.into_iter();
+            let first = values
+                .next()
+                .ok_or_else(|| RuntimeError::invalid_children(name, "at least one child"))?;
+
+            values.try_fold(first, |current, value| {
+                let ordering =
+                    current
+                        .partial_cmp(&value)
+                        .ok_or_else(|| RuntimeError::IncompatibleTypes {
+                            operation: "compare".to_string(),
+                            a: current.clone(),
+                            b: Some(value.clone()),
+                        })?;
+
+                Ok::<_, RuntimeError>(match (name, ordering) {
+                    ("min", Ordering::Greater) | ("max", Ordering::Less) => value,
+                    _ => current,
+                })
+            })?
+        }
+
+        "clamp" => {
+            let (value, arguments) = operands(
+                element,
+                "clamp",
+                &["min", "max"],
+                depth,
+                variables,
+                specials,
+                state,
+            )?;
+            let [min, max] = <[_; 2]>::try_from(arguments).unwrap_or_default();
+
+            let min = min.ok_or_else(|| RuntimeError::missing_attribute("clamp", "min"))?;
+            let max = max.ok_or_else(|| RuntimeError::missing_attribute("clamp", "max"))?;
+
+            match (&value, min.as_int(), max.as_int()) {
+                (Value::Int(i), Some(min), Some(max)) if min <= max => {
+                    Value::Int((*i).clamp(min, max))
+                }
+                _ => {
+                    let (min, max) = (float_operand(&min)?, float_operand(&max)?);
+                    ensure!(min <= max, RuntimeError::InvalidRange("clamp".to_string()));
+
+                    Value::Float(float_operand(&value)?.clamp(min, max))
+                }
+            }
+        }
+
+        name @ ("pi" | "e" | "tau" | "inf" | "infinity" | "nan") => {
+            ensure!(
+                element.children.is_empty(),
+                RuntimeError::invalid_children(name, "no children")
+            );
+
+            Value::Float(match name {
+                "pi" => std::f64::consts::PI,
+                "e" => std::f64::consts::E,
+                "tau" => std::f64::consts::TAU,
+                "nan" => f64::NAN,
+                _ => f64::INFINITY,
+            })
+        }
+
+        "is-nan" => {
+            let (value, _) = operands(element, "is-nan", &[], depth, variables, specials, state)?;
+
+            Value::Bool(matches!(value, Value::Float(f) if f.is_nan()))
         }
 
         name @ ("eq" | "equals" | "equal") => {
             ensure!(
                 element.children.len() >= 2,
-                "Expected at least 2 children in <{name}> element"
+                RuntimeError::invalid_children(name, "at least 2 children")
             );
 
             let values = element
                 .children
                 .iter()
-                .map(|child| interpret(child, depth + 1, variables, specials, functions))
+                .map(|child| interpret(child, depth + 1, variables, specials, state))
                 .collect::<Result<Vec<Value>>>()?;
 
             let all_equal = values.windows(2).all(|w| w[0] == w[1]);
@@ -570,13 +1748,13 @@ pub fn interpret(
         name @ ("ne" | "not-equals" | "not-equal") => {
             ensure!(
                 element.children.len() >= 2,
-                "Expected at least 2 children in <{name}> element"
+                RuntimeError::invalid_children(name, "at least 2 children")
             );
 
             let values = element
                 .children
                 .iter()
-                .map(|child| interpret(child, depth + 1, variables, specials, functions))
+                .map(|child| interpret(child, depth + 1, variables, specials, state))
                 .collect::<Result<Vec<Value>>>()?;
 
             let all_not_equal = values.windows(2).all(|w| w[0] != w[1]);
@@ -586,13 +1764,13 @@ pub fn interpret(
         name @ ("lt" | "less-than") => {
             ensure!(
                 element.children.len() >= 2,
-                "Expected at least 2 children in <{name}> element"
+                RuntimeError::invalid_children(name, "at least 2 children")
             );
 
             let values = element
                 .children
                 .iter()
-                .map(|child| interpret(child, depth + 1, variables, specials, functions))
+                .map(|child| interpret(child, depth + 1, variables, specials, state))
                 .collect::<Result<Vec<Value>>>()?;
 
             let all_less_than = values.windows(2).all(|w| w[0] < w[1]);
@@ -603,13 +1781,13 @@ pub fn interpret(
         name @ ("le" | "less-than-or-equal") => {
             ensure!(
                 element.children.len() >= 2,
-                "Expected at least 2 children in <{name}> element"
+                RuntimeError::invalid_children(name, "at least 2 children")
             );
 
             let values = element
                 .children
                 .iter()
-                .map(|child| interpret(child, depth + 1, variables, specials, functions))
+                .map(|child| interpret(child, depth + 1, variables, specials, state))
                 .collect::<Result<Vec<Value>>>()?;
 
             let all_less_than_or_equal = values.windows(2).all(|w| w[0] <= w[1]);
@@ -620,13 +1798,13 @@ pub fn interpret(
         name @ ("gt" | "greater-than") => {
             ensure!(
                 element.children.len() >= 2,
-                "Expected at least 2 children in <{name}> element"
+                RuntimeError::invalid_children(name, "at least 2 children")
             );
 
             let values = element
                 .children
                 .iter()
-                .map(|child| interpret(child, depth + 1, variables, specials, functions))
+                .map(|child| interpret(child, depth + 1, variables, specials, state))
                 .collect::<Result<Vec<Value>>>()?;
 
             let all_greater_than = values.windows(2).all(|w| w[0] > w[1]);
@@ -637,13 +1815,13 @@ pub fn interpret(
         name @ ("ge" | "greater-than-or-equal") => {
             ensure!(
                 element.children.len() >= 2,
-                "Expected at least 2 children in <{name}> element"
+                RuntimeError::invalid_children(name, "at least 2 children")
             );
 
             let values = element
                 .children
                 .iter()
-                .map(|child| interpret(child, depth + 1, variables, specials, functions))
+                .map(|child| interpret(child, depth + 1, variables, specials, state))
                 .collect::<Result<Vec<Value>>>()?;
 
             let all_greater_than_or_equal = values.windows(2).all(|w| w[0] >= w[1]);
@@ -654,13 +1832,13 @@ pub fn interpret(
         "starts-with" => {
             ensure!(
                 element.children.len() == 2,
-                "Expected exactly 2 children in <starts-with> element"
+                RuntimeError::invalid_children("starts-with", "exactly 2 children")
             );
 
             let values = element
                 .children
                 .iter()
-                .map(|child| interpret(child, depth + 1, variables, specials, functions))
+                .map(|child| interpret(child, depth + 1, variables, specials, state))
                 .collect::<Result<Vec<Value>>>()?;
 
             let left = &values[0];
@@ -672,13 +1850,13 @@ pub fn interpret(
         "ends-with" => {
             ensure!(
                 element.children.len() == 2,
-                "Expected exactly 2 children in <ends-with> element"
+                RuntimeError::invalid_children("ends-with", "exactly 2 children")
             );
 
             let values = element
                 .children
                 .iter()
-                .map(|child| interpret(child, depth + 1, variables, specials, functions))
+                .map(|child| interpret(child, depth + 1, variables, specials, state))
                 .collect::<Result<Vec<Value>>>()?;
 
             let left = &values[0];
@@ -690,13 +1868,13 @@ pub fn interpret(
         "contains" => {
             ensure!(
                 element.children.len() == 2,
-                "Expected exactly 2 children in <contains> element"
+                RuntimeError::invalid_children("contains", "exactly 2 children")
             );
 
             let values = element
                 .children
                 .iter()
-                .map(|child| interpret(child, depth + 1, variables, specials, functions))
+                .map(|child| interpret(child, depth + 1, variables, specials, state))
                 .collect::<Result<Vec<Value>>>()?;
 
             let left = &values[0];
@@ -705,189 +1883,576 @@ pub fn interpret(
             Value::Bool(left.to_string().contains(&right.to_string()))
         }
 
-        "try" => {
-            ensure!(
-                element.children.len() == 2,
-                "Expected exactly 2 children in <try> element"
-            );
-
-            let do_block = element
-                .children
-                .iter()
-                .find(|child| child.name.to_lowercase() == "do")
-                .wrap_err("Expected a <do> child in <try> element")?;
-
-            let catch_block = element
-                .children
-                .iter()
-                .find(|child| child.name.to_lowercase() == "catch")
-                .wrap_err("Expected a <catch> child in <try> element")?;
+        name @ ("length" | "len") => {
+            let (value, _) = operands(element, name, &[], depth, variables, specials, state)?;
 
-            let ret = do_block.children.iter().try_fold(Value::Null, |_, child| {
-                interpret(child, depth + 1, variables, specials, functions)
-            });
+            Value::Int(match &value {
+                Value::List(items) => items.len(),
+                value => graphemes(&value.to_string()).len(),
+            } as i64)
+        }
 
-            match ret {
-                Ok(val) => val,
-                Err(err) => match err.downcast::<BlockControl>() {
-                    Ok(BlockControl::Break(val)) => val,
-                    Ok(BlockControl::Continue) => return Err(BlockControl::Continue.into()),
+        "substring" => {
+            let (value, arguments) = operands(
+                element,
+                "substring",
+                &["start", "end"],
+                depth,
+                variables,
+                specials,
+                state,
+            )?;
+            let [start, end] = <[_; 2]>::try_from(arguments).unwrap_or_default();
 
-                    Err(err) => {
-                        let err_val = Value::from(err.to_string());
-                        let specials =
-                            [&[HashMap::from([("error".to_string(), err_val)])], specials].concat();
+            let text = value.to_string();
+            let graphemes = graphemes(&text);
 
-                        match catch_block
-                            .children
-                            .iter()
-                            .try_fold(Value::Null, |_, child| {
-                                interpret(child, depth + 1, variables, &specials, functions)
-                            }) {
-                            Ok(val) => val,
-                            Err(err) => match err.downcast::<BlockControl>() {
-                                Ok(BlockControl::Break(val)) => val,
-                                Ok(BlockControl::Continue) => {
-                                    return Err(BlockControl::Continue.into());
-                                }
-                                Err(err) => return Err(err),
-                            },
-                        }
-                    }
-                },
-            }
-        }
+            let start = resolve_index(int_operand(start)?.unwrap_or(0), graphemes.len());
+            let end = resolve_index(
+                int_operand(end)?.unwrap_or(graphemes.len() as i64),
+                graphemes.len(),
+            );
 
-        "block" => {
-            match element.children.iter().try_fold(Value::Null, |_, child| {
-                interpret(child, depth + 1, variables, specials, functions)
-            }) {
-                Ok(val) => val,
-                Err(err) => match err.downcast::<BlockControl>() {
-                    Ok(BlockControl::Break(val)) => val,
-                    Ok(BlockControl::Continue) => return Err(BlockControl::Continue.into()),
-                    Err(err) => return Err(err),
-                },
-            }
+            Value::Str(graphemes[start..end.max(start)].concat())
         }
 
-        "if" => {
+        "index-of" => {
             ensure!(
-                element.children.len() >= 2,
-                "Expected at least 2 children in <if> element"
+                element.children.len() == 2,
+                RuntimeError::invalid_children("index-of", "exactly 2 children")
             );
 
-            let mut condition_count = 0;
-            let mut then_count = 0;
-            let mut else_count = 0;
+            let values = element
+                .children
+                .iter()
+                .map(|child| interpret(child, depth + 1, variables, specials, state))
+                .collect::<Result<Vec<Value>>>()?;
 
-            for child in element.children.iter() {
-                let key = child.name.to_lowercase();
-                match key.as_str() {
-                    "condition" => condition_count += 1,
-                    "then" => then_count += 1,
-                    "elif" => {}
-                    "else" => else_count += 1,
-                    _ => bail!("Unexpected child in <if> element: {}", child.name),
+            let index = match (&values[0], &values[1]) {
+                (Value::List(items), needle) => items.iter().position(|item| item == needle),
+                (value, needle) => {
+                    let text = value.to_string();
+                    let needle = needle.to_string();
+
+                    text.grapheme_indices(true)
+                        .map(|(index, _)| index)
+                        .chain([text.len()])
+                        .position(|index| text[index..].starts_with(&needle))
                 }
-            }
+            };
 
+            index.map_or(Value::Null, |index| Value::Int(index as i64))
+        }
+
+        "replace" => {
             ensure!(
-                condition_count == 1,
-                "Expected exactly one <condition> child in <if> element"
-            );
-            ensure!(
-                then_count == 1,
-                "Expected exactly one <then> child in <if> element"
-            );
-            ensure!(
-                else_count <= 1,
-                "Expected at most one <else> child in <if> element"
+                element.children.len() == 3,
+                RuntimeError::invalid_children("replace", "exactly 3 children")
             );
 
-            let condition = element
-                .children
-                .iter()
-                .find(|child| child.name.to_lowercase() == "condition")
-                .wrap_err("Expected a <condition> child in <if> element")?;
+            let count = element
+                .attributes
+                .get("count")
+                .map(|s| {
+                    s.parse::<usize>()
+                        .map_err(|_| RuntimeError::InvalidAttribute {
+                            element: "replace".to_string(),
+                            attribute: "count".to_string(),
+                            value: s.clone(),
+                        })
+                })
+                .transpose()?;
 
-            let then_block = element
+            let values = element
                 .children
                 .iter()
-                .find(|child| child.name.to_lowercase() == "then")
-                .wrap_err("Expected a <then> child in <if> element")?;
+                .map(|child| interpret(child, depth + 1, variables, specials, state))
+                .collect::<Result<Vec<Value>>>()?;
 
-            let elif_blocks: Vec<&Element> = element
-                .children
-                .iter()
-                .filter(|child| child.name.to_lowercase() == "elif")
-                .collect();
+            let text = values[0].to_string();
+            let from = values[1].to_string();
+            let to = values[2].to_string();
 
-            let else_block = element
-                .children
-                .iter()
-                .find(|child| child.name.to_lowercase() == "else");
+            Value::Str(match count {
+                Some(count) => text.replacen(&from, &to, count),
+                None => text.replace(&from, &to),
+            })
+        }
 
-            for elif_block in &elif_blocks {
-                ensure!(
-                    elif_block.children.len() == 2,
-                    "Expected exactly 2 children in <elif> element"
-                );
+        "split" => {
+            let (value, arguments) = operands(
+                element,
+                "split",
+                &["separator"],
+                depth,
+                variables,
+                specials,
+                state,
+            )?;
+            let [separator] = <[_; 1]>::try_from(arguments).unwrap_or_default();
 
-                let mut elif_condition_count = 0;
-                let mut elif_then_count = 0;
+            let text = value.to_string();
 
-                for child in elif_block.children.iter() {
-                    let key = child.name.to_lowercase();
-                    match key.as_str() {
+            let parts: Vec<&str> = match separator.map(|separator| separator.to_string()) {
+                None => text.split_whitespace().collect(),
+                Some(separator) if separator.is_empty() => graphemes(&text),
+                Some(separator) => text.split(&separator).collect(),
+            };
+
+            Value::List(parts.into_iter().map(Value::from).collect())
+        }
+
+        name @ ("upper" | "uppercase") => {
+            let (value, _) = operands(element, name, &[], depth, variables, specials, state)?;
+
+            Value::Str(value.to_string().to_uppercase())
+        }
+
+        name @ ("lower" | "lowercase") => {
+            let (value, _) = operands(element, name, &[], depth, variables, specials, state)?;
+
+            Value::Str(value.to_string().to_lowercase())
+        }
+
+        "repeat" => {
+            let (value, arguments) = operands(
+                element,
+                "repeat",
+                &["count"],
+                depth,
+                variables,
+                specials,
+                state,
+            )?;
+            let [count] = <[_; 1]>::try_from(arguments).unwrap_or_default();
+
+            let count = int_operand(count)?
+                .ok_or_else(|| RuntimeError::missing_attribute("repeat", "count"))?
+                .max(0) as usize;
+
+            let text = value.to_string();
+            state.check_string_length(text.len().saturating_mul(count))?;
+
+            Value::Str(text.repeat(count))
+        }
+
+        name @ ("pad-start" | "pad-end") => {
+            let (value, arguments) = operands(
+                element,
+                name,
+                &["length", "fill"],
+                depth,
+                variables,
+                specials,
+                state,
+            )?;
+            let [length, fill] = <[_; 2]>::try_from(arguments).unwrap_or_default();
+
+            let length = int_operand(length)?
+                .ok_or_else(|| RuntimeError::missing_attribute(name, "length"))?
+                .max(0) as usize;
+            let fill = fill.map_or_else(|| " ".to_string(), |fill| fill.to_string());
+
+            let text = value.to_string();
+            let missing = length.saturating_sub(graphemes(&text).len());
+
+            if missing == 0 || fill.is_empty() {
+                Value::Str(text)
+            } else {
+                state.check_string_length(
+                    text.len()
+                        .saturating_add(missing.saturating_mul(fill.len())),
+                )?;
+
+                let padding = graphemes(&fill)
+                    .into_iter()
+                    .cycle()
+                    .take(missing)
+                    .collect::<String>();
+
+                Value::Str(if name == "pad-start" {
+                    padding + &text
+                } else {
+                    text + &padding
+                })
+            }
+        }
+
+        "char-at" => {
+            let (value, arguments) = operands(
+                element,
+                "char-at",
+                &["index"],
+                depth,
+                variables,
+                specials,
+                state,
+            )?;
+            let [index] = <[_; 1]>::try_from(arguments).unwrap_or_default();
+
+            let index = int_operand(index)?
+                .ok_or_else(|| RuntimeError::missing_attribute("char-at", "index"))?;
+
+            let text = value.to_string();
+            let graphemes = graphemes(&text);
+
+            let index = if index < 0 {
+                graphemes.len().checked_sub(index.unsigned_abs() as usize)
+            } else {
+                Some(index as usize)
+            };
+
+            index
+                .and_then(|index| graphemes.get(index))
+                .map_or(Value::Null, |grapheme| Value::from(*grapheme))
+        }
+
+        "chars" => {
+            let (value, _) = operands(element, "chars", &[], depth, variables, specials, state)?;
+
+            Value::List(
+                graphemes(&value.to_string())
+                    .into_iter()
+                    .map(Value::from)
+                    .collect(),
+            )
+        }
+
+        "reverse" => {
+            let (value, _) = operands(element, "reverse", &[], depth, variables, specials, state)?;
+
+            match value {
+                Value::List(mut items) => {
+                    items.reverse();
+                    Value::List(items)
+                }
+                value => Value::Str(graphemes(&value.to_string()).into_iter().rev().collect()),
+            }
+        }
+
+        "matches" => {
+            let regex = state.pattern(element)?;
+            let (value, _) = operands(element, "matches", &[], depth, variables, specials, state)?;
+
+            Value::Bool(regex.is_match(&value.to_string()))
+        }
+
+        "find" => {
+            let regex = state.pattern(element)?;
+            let (value, _) = operands(element, "find", &[], depth, variables, specials, state)?;
+
+            let all = element
+                .attributes
+                .get("all")
+                .is_some_and(|s| Value::from(s.as_str()).as_bool());
+
+            // the whole match followed by every group, with null for the groups that didn't participate
+            let groups = |captures: regex::Captures| {
+                Value::List(
+                    captures
+                        .iter()
+                        .map(|group| group.map_or(Value::Null, |group| group.as_str().into()))
+                        .collect(),
+                )
+            };
+
+            let text = value.to_string();
+
+            if all {
+                Value::List(regex.captures_iter(&text).map(groups).collect())
+            } else {
+                regex.captures(&text).map_or(Value::Null, groups)
+            }
+        }
+
+        "replace-regex" => {
+            let regex = state.pattern(element)?;
+            let (value, arguments) = operands(
+                element,
+                "replace-regex",
+                &["replacement"],
+                depth,
+                variables,
+                specials,
+                state,
+            )?;
+            let [replacement] = <[_; 1]>::try_from(arguments).unwrap_or_default();
+
+            let replacement = replacement
+                .ok_or_else(|| RuntimeError::missing_attribute("replace-regex", "replacement"))?
+                .to_string();
+
+            let count = element
+                .attributes
+                .get("count")
+                .map(|s| {
+                    s.parse::<usize>()
+                        .map_err(|_| RuntimeError::InvalidAttribute {
+                            element: "replace-regex".to_string(),
+                            attribute: "count".to_string(),
+                            value: s.clone(),
+                        })
+                })
+                .transpose()?
+                .unwrap_or(0);
+
+            Value::Str(
+                regex
+                    .replacen(&value.to_string(), count, replacement.as_str())
+                    .into_owned(),
+            )
+        }
+
+        "split-regex" => {
+            let regex = state.pattern(element)?;
+            let (value, _) = operands(
+                element,
+                "split-regex",
+                &[],
+                depth,
+                variables,
+                specials,
+                state,
+            )?;
+
+            Value::List(regex.split(&value.to_string()).map(Value::from).collect())
+        }
+
+        "try" => {
+            let mut do_block = None;
+            let mut catch_blocks = Vec::new();
+            let mut finally_block = None;
+
+            for child in element.children.iter() {
+                match child.name.to_lowercase().as_str() {
+                    "do" => {
+                        ensure!(
+                            do_block.is_none(),
+                            RuntimeError::invalid_children("try", "exactly one <do> child")
+                        );
+                        do_block = Some(child);
+                    }
+                    "catch" => catch_blocks.push(child),
+                    "finally" => {
+                        ensure!(
+                            finally_block.is_none(),
+                            RuntimeError::invalid_children("try", "at most one <finally> child")
+                        );
+                        finally_block = Some(child);
+                    }
+                    _ => bail!(RuntimeError::unexpected_child("try", &child.name)),
+                }
+            }
+
+            let do_block =
+                do_block.ok_or_else(|| RuntimeError::invalid_children("try", "a <do> child"))?;
+
+            ensure!(
+                !catch_blocks.is_empty() || finally_block.is_some(),
+                RuntimeError::invalid_children("try", "a <catch> or <finally> child")
+            );
+
+            let ret = match run_block(do_block, depth, variables, specials, state) {
+                Err(err) if err.is::<BlockControl>() => Err(err),
+
+                Err(err)
+                    if matches!(
+                        RuntimeError::find(&err),
+                        Some(RuntimeError::LimitExceeded(limit))
+                            if !state.limits.catchable || !limit.is_catchable()
+                    ) =>
+                {
+                    Err(err)
+                }
+
+                Err(err) => {
+                    let kind = error_kind(&err);
+                    let code = err.code().map(|code| code.to_string());
+
+                    let catch_block = catch_blocks.iter().find(|catch_block| {
+                        catch_block.attributes.get("kind").is_none_or(|kinds| {
+                            kinds
+                                .split_whitespace()
+                                .any(|k| k == kind || code.as_deref() == Some(k))
+                        })
+                    });
+
+                    if let Some(catch_block) = catch_block {
+                        let data = match RuntimeError::find(&err) {
+                            Some(RuntimeError::Thrown { data, .. }) => data.clone(),
+                            _ => Value::Null,
+                        };
+
+                        let trace = state.take_trace();
+                        let stack = trace
+                            .iter()
+                            .rev()
+                            .map(|frame| Value::from(frame.to_string()))
+                            .collect::<Vec<_>>();
+
+                        let scope = HashMap::from([
+                            ("error".to_string(), Value::from(err.to_string())),
+                            ("error_kind".to_string(), Value::from(kind)),
+                            (
+                                "error_code".to_string(),
+                                code.map(Value::from).unwrap_or_default(),
+                            ),
+                            ("error_data".to_string(), data),
+                            ("stack".to_string(), Value::List(stack)),
+                        ]);
+                        let specials = specials.with(&scope);
+
+                        state.caught.push(Some((err, trace)));
+                        let ret = run_block(catch_block, depth, variables, &specials, state);
+                        state.caught.pop();
+
+                        ret
+                    } else {
+                        Err(err)
+                    }
+                }
+
+                ret => ret,
+            };
+
+            if let Some(finally_block) = finally_block {
+                run_block(finally_block, depth, variables, specials, state)?;
+            }
+
+            match ret {
+                Ok(val) => val,
+                Err(err) => match err.downcast::<BlockControl>() {
+                    Ok(BlockControl::Break(val)) => val,
+                    Ok(control) => return Err(control.into()),
+                    Err(err) => return Err(err),
+                },
+            }
+        }
+
+        "block" => {
+            match element.children.iter().try_fold(Value::Null, |_, child| {
+                interpret(child, depth + 1, variables, specials, state)
+            }) {
+                Ok(val) => val,
+                Err(err) => match err.downcast::<BlockControl>() {
+                    Ok(BlockControl::Break(val)) => val,
+                    Ok(control) => return Err(control.into()),
+                    Err(err) => return Err(err),
+                },
+            }
+        }
+
+        "if" => {
+            ensure!(
+                element.children.len() >= 2,
+                RuntimeError::invalid_children("if", "at least 2 children")
+            );
+
+            let mut condition_count = 0;
+            let mut then_count = 0;
+            let mut else_count = 0;
+
+            for child in element.children.iter() {
+                let key = child.name.to_lowercase();
+                match key.as_str() {
+                    "condition" => condition_count += 1,
+                    "then" => then_count += 1,
+                    "elif" => {}
+                    "else" => else_count += 1,
+                    _ => bail!(RuntimeError::unexpected_child("if", &child.name)),
+                }
+            }
+
+            ensure!(
+                condition_count == 1,
+                RuntimeError::invalid_children("if", "exactly one <condition> child")
+            );
+            ensure!(
+                then_count == 1,
+                RuntimeError::invalid_children("if", "exactly one <then> child")
+            );
+            ensure!(
+                else_count <= 1,
+                RuntimeError::invalid_children("if", "at most one <else> child")
+            );
+
+            let condition = element
+                .children
+                .iter()
+                .find(|child| child.name.to_lowercase() == "condition")
+                .ok_or_else(|| RuntimeError::invalid_children("if", "a <condition> child"))?;
+
+            let then_block = element
+                .children
+                .iter()
+                .find(|child| child.name.to_lowercase() == "then")
+                .ok_or_else(|| RuntimeError::invalid_children("if", "a <then> child"))?;
+
+            let elif_blocks: Vec<&Element> = element
+                .children
+                .iter()
+                .filter(|child| child.name.to_lowercase() == "elif")
+                .collect();
+
+            let else_block = element
+                .children
+                .iter()
+                .find(|child| child.name.to_lowercase() == "else");
+
+            for elif_block in &elif_blocks {
+                ensure!(
+                    elif_block.children.len() == 2,
+                    RuntimeError::invalid_children("elif", "exactly 2 children")
+                );
+
+                let mut elif_condition_count = 0;
+                let mut elif_then_count = 0;
+
+                for child in elif_block.children.iter() {
+                    let key = child.name.to_lowercase();
+                    match key.as_str() {
                         "condition" => elif_condition_count += 1,
                         "then" => elif_then_count += 1,
-                        _ => bail!("Unexpected child in <elif> element: {}", child.name),
+                        _ => bail!(RuntimeError::unexpected_child("elif", &child.name)),
                     }
                 }
 
                 ensure!(
                     elif_condition_count == 1,
-                    "Expected exactly one <condition> child in <elif> element"
+                    RuntimeError::invalid_children("elif", "exactly one <condition> child")
                 );
                 ensure!(
                     elif_then_count == 1,
-                    "Expected exactly one <then> child in <elif> element"
+                    RuntimeError::invalid_children("elif", "exactly one <then> child")
                 );
             }
 
             ensure!(
                 condition.children.len() == 1,
-                "Expected exactly one child in <condition> element"
+                RuntimeError::invalid_children("condition", "exactly one child")
             );
             let condition_value = interpret(
                 &condition.children[0],
                 depth + 2,
                 variables,
                 specials,
-                functions,
+                state,
             )?;
 
-            let specials = [
-                &[HashMap::from([(
-                    "condition".to_string(),
-                    condition_value.clone(),
-                )])],
-                specials,
-            ]
-            .concat();
+            let scope = HashMap::from([("condition".to_string(), condition_value.clone())]);
+            let specials = specials.with(&scope);
 
             if condition_value.as_bool() {
                 match then_block
                     .children
                     .iter()
                     .try_fold(Value::Null, |_, child| {
-                        interpret(child, depth + 1, variables, &specials, functions)
+                        interpret(child, depth + 1, variables, &specials, state)
                     }) {
                     Ok(val) => val,
                     Err(err) => match err.downcast::<BlockControl>() {
                         Ok(BlockControl::Break(val)) => val,
-                        Ok(BlockControl::Continue) => return Err(BlockControl::Continue.into()),
+                        Ok(control) => return Err(control.into()),
                         Err(err) => return Err(err),
                     },
                 }
@@ -907,35 +2472,30 @@ pub fn interpret(
 
                     ensure!(
                         elif_condition.children.len() == 1,
-                        "Expected exactly one child in <condition> element"
+                        RuntimeError::invalid_children("condition", "exactly one child")
                     );
                     let elif_condition_value = interpret(
                         &elif_condition.children[0],
                         depth + 2,
                         variables,
                         &specials,
-                        functions,
+                        state,
                     )?;
 
-                    let elif_specials = [
-                        &[HashMap::from([(
-                            "condition".to_string(),
-                            elif_condition_value.clone(),
-                        )])],
-                        &specials[..],
-                    ]
-                    .concat();
+                    let elif_scope =
+                        HashMap::from([("condition".to_string(), elif_condition_value.clone())]);
+                    let elif_specials = specials.with(&elif_scope);
 
                     if elif_condition_value.as_bool() {
                         return Ok(
                             match elif_then.children.iter().try_fold(Value::Null, |_, child| {
-                                interpret(child, depth + 1, variables, &elif_specials, functions)
+                                interpret(child, depth + 1, variables, &elif_specials, state)
                             }) {
                                 Ok(val) => val,
                                 Err(err) => match err.downcast::<BlockControl>() {
                                     Ok(BlockControl::Break(val)) => val,
-                                    Ok(BlockControl::Continue) => {
-                                        return Err(BlockControl::Continue.into());
+                                    Ok(control) => {
+                                        return Err(control.into());
                                     }
                                     Err(err) => return Err(err),
                                 },
@@ -949,12 +2509,12 @@ pub fn interpret(
                         .children
                         .iter()
                         .try_fold(Value::Null, |_, child| {
-                            interpret(child, depth + 1, variables, &specials, functions)
+                            interpret(child, depth + 1, variables, &specials, state)
                         }) {
                         Ok(val) => val,
                         Err(err) => match err.downcast::<BlockControl>() {
                             Ok(BlockControl::Break(val)) => val,
-                            Ok(BlockControl::Continue) => return Err(BlockControl::Continue.into()),
+                            Ok(control) => return Err(control.into()),
                             Err(err) => return Err(err),
                         },
                     }
@@ -964,147 +2524,940 @@ pub fn interpret(
             }
         }
 
-        "loop" => {
-            let start = element
-                .attributes
-                .get("start")
-                .and_then(|s| s.parse::<i64>().ok())
-                .unwrap_or(0);
+        "match" => {
+            let Some((subject, arms)) = element.children.split_first() else {
+                bail!(RuntimeError::invalid_children(
+                    "match",
+                    "a subject followed by <case> and <default> children"
+                ));
+            };
 
-            let end = element
-                .attributes
-                .get("end")
-                .and_then(|s| s.parse::<i64>().ok());
+            ensure!(
+                !matches!(subject.name.to_lowercase().as_str(), "case" | "default"),
+                RuntimeError::invalid_children("match", "a subject as the first child")
+            );
 
-            let mut iteration = start;
+            let mut cases = Vec::new();
+            let mut defaults = Vec::new();
 
-            let mut specials = [
-                &[HashMap::from([(
-                    "iteration".to_string(),
-                    Value::Int(iteration),
-                )])],
-                specials,
-            ]
-            .concat();
+            for arm in arms {
+                match arm.name.to_lowercase().as_str() {
+                    "case" => cases.push(arm),
+                    "default" => defaults.push(arm),
+                    _ => bail!(RuntimeError::unexpected_child("match", &arm.name)),
+                }
+            }
 
-            'outer: loop {
-                specials[0].insert("iteration".to_string(), Value::Int(iteration));
+            ensure!(
+                defaults.len() <= 1,
+                RuntimeError::invalid_children("match", "at most one <default> child")
+            );
 
-                if let Some(end) = end
-                    && iteration >= end
+            let subject = interpret(subject, depth + 1, variables, specials, state)?;
+
+            let scope = HashMap::from([("subject".to_string(), subject.clone())]);
+            let specials = specials.with(&scope);
+
+            let mut chosen = defaults
+                .first()
+                .map(|default| (*default, None, HashMap::new()));
+
+            for case in cases {
+                let attribute = |name: &str| case.attributes.get(name);
+
+                if let Some(value) = attribute("value")
+                    && subject.to_string() != *value
                 {
-                    break 'outer Ok(Value::Null);
+                    continue;
                 }
 
-                for child in &element.children {
-                    if let Err(err) = interpret(child, depth + 1, variables, &specials, functions) {
-                        match err.downcast::<BlockControl>() {
-                            Err(e) => break 'outer Err(e),
-                            Ok(BlockControl::Break(value)) => break 'outer Ok(value),
-                            Ok(BlockControl::Continue) => continue 'outer,
-                        }
-                    }
+                if let Some(types) = attribute("type")
+                    && !types
+                        .split_whitespace()
+                        .any(|t| t.eq_ignore_ascii_case(&subject.type_name()))
+                {
+                    continue;
                 }
 
-                iteration += 1;
-            }?
-        }
+                if let Some(prefix) = attribute("prefix")
+                    && !subject.to_string().starts_with(prefix.as_str())
+                {
+                    continue;
+                }
 
-        "function" => {
-            let name = element
-                .attributes
-                .get("name")
-                .wrap_err("Expected the `name` attribute in <function> element")?
-                .clone();
+                // the fields of a matched variant are available as specials in the guard and the block
+                let mut bindings = HashMap::new();
+                if let Some(variants) = attribute("variant") {
+                    let Value::Enum(variant) = &subject else {
+                        continue;
+                    };
+
+                    if !variants.split_whitespace().any(|v| *v == variant.variant)
+                        || element
+                            .attributes
+                            .get("enum")
+                            .is_some_and(|name| *name != variant.name)
+                    {
+                        continue;
+                    }
 
-            ensure!(
-                !name.is_empty(),
-                "Function name cannot be empty in <function> element"
-            );
+                    bindings.extend(variant.fields.iter().cloned());
+                }
 
-            functions.insert(name, element.children.clone());
+                // strings are compared with string bounds, and anything else with number bounds
+                let bound = |name: &str| -> Result<Option<Value>, RuntimeError> {
+                    let Some(text) = attribute(name) else {
+                        return Ok(None);
+                    };
 
-            Value::Null
-        }
+                    if let Value::Str(_) = subject {
+                        return Ok(Some(Value::Str(text.clone())));
+                    }
 
-        "call" => {
-            let name = element
-                .attributes
-                .get("name")
-                .wrap_err("Expected the `name` attribute in <call> element")?
-                .clone();
+                    text.parse::<i64>()
+                        .map(Value::Int)
+                        .or_else(|_| text.parse::<f64>().map(Value::Float))
+                        .map(Some)
+                        .map_err(|_| RuntimeError::InvalidAttribute {
+                            element: "case".to_string(),
+                            attribute: name.to_string(),
+                            value: text.clone(),
+                        })
+                };
 
-            let func = functions
-                .get(&name)
-                .cloned()
-                .wrap_err(format!("Function `{name}` not found"))?;
+                if let Some(min) = bound("min")?
+                    && !subject.partial_cmp(&min).is_some_and(Ordering::is_ge)
+                {
+                    continue;
+                }
 
-            let children = element
-                .children
-                .iter()
-                .map(|child| interpret(child, depth + 1, variables, specials, functions))
-                .collect::<Result<Vec<Value>>>()?;
+                if let Some(max) = bound("max")?
+                    && !subject.partial_cmp(&max).is_some_and(Ordering::is_le)
+                {
+                    continue;
+                }
 
-            let child_count = children.len();
+                let guards: Vec<&Element> = case
+                    .children
+                    .iter()
+                    .filter(|child| child.name.to_lowercase() == "condition")
+                    .collect();
+
+                let guard = match guards.as_slice() {
+                    [] => None,
+                    [guard] => {
+                        ensure!(
+                            guard.children.len() == 1,
+                            RuntimeError::invalid_children("condition", "exactly one child")
+                        );
 
-            let children_specials = children
-                .into_iter()
-                .enumerate()
-                .map(|(i, value)| (format!("child:{i}"), value))
-                .chain(std::iter::once((
-                    String::from("child_count"),
-                    Value::Int(child_count as i64),
-                )))
-                .collect::<HashMap<_, _>>();
-
-            let attrs = element
-                .attributes
-                .iter()
-                .map(|(k, v)| (k.clone(), Value::from(v.as_str())))
-                .collect::<HashMap<_, _>>();
+                        let specials = specials.with(&bindings);
+                        let value =
+                            interpret(&guard.children[0], depth + 3, variables, &specials, state)?;
+                        if !value.as_bool() {
+                            continue;
+                        }
 
-            let specials = [&[attrs, children_specials], specials].concat();
+                        Some(value)
+                    }
+                    _ => bail!(RuntimeError::invalid_children(
+                        "case",
+                        "at most one <condition> child"
+                    )),
+                };
+
+                chosen = Some((case, guard, bindings));
+                break;
+            }
 
-            let mut variables = variables.clone();
+            let Some((arm, guard, bindings)) = chosen else {
+                return Ok(Value::Null);
+            };
 
-            match func.into_iter().try_fold(Value::Null, |_, child| {
-                interpret(&child, depth + 1, &mut variables, &specials, functions)
+            let mut scope = bindings;
+            if let Some(condition) = guard {
+                scope.insert("condition".to_string(), condition);
+            }
+            let specials = specials.with(&scope);
+
+            let mut body = arm
+                .children
+                .iter()
+                .filter(|child| child.name.to_lowercase() != "condition");
+
+            match body.try_fold(Value::Null, |_, child| {
+                interpret(child, depth + 2, variables, &specials, state)
             }) {
                 Ok(val) => val,
                 Err(err) => match err.downcast::<BlockControl>() {
                     Ok(BlockControl::Break(val)) => val,
-                    Ok(BlockControl::Continue) => return Err(BlockControl::Continue.into()),
+                    Ok(control) => return Err(control.into()),
+                    Err(err) => return Err(err),
+                },
+            }
+        }
+
+        "loop" => {
+            let start = loop_bound(element, "start", depth, variables, specials, state)?;
+            let end = loop_bound(element, "end", depth, variables, specials, state)?;
+            let step = loop_bound(element, "step", depth, variables, specials, state)?;
+
+            let step = step.unwrap_or(1);
+            ensure!(
+                step != 0,
+                RuntimeError::InvalidAttribute {
+                    element: "loop".to_string(),
+                    attribute: "step".to_string(),
+                    value: step.to_string(),
+                }
+            );
+
+            let var = element.attributes.get("var");
+            let body: Vec<&Element> = element
+                .children
+                .iter()
+                .filter(|child| {
+                    !matches!(child.name.to_lowercase().as_str(), "start" | "end" | "step")
+                })
+                .collect();
+
+            let mut iteration = start.unwrap_or(0);
+
+            'outer: loop {
+                // a negative step counts down to `end` instead of up to it
+                if let Some(end) = end
+                    && (if step > 0 {
+                        iteration >= end
+                    } else {
+                        iteration <= end
+                    })
+                {
+                    break 'outer Ok(Value::Null);
+                }
+
+                let scope = HashMap::from([("iteration".to_string(), Value::Int(iteration))]);
+                let specials = specials.with(&scope);
+                if let Some(var) = var
+                    && let Err(err) = variables.set(var, Value::Int(iteration))
+                {
+                    break 'outer Err(err.into());
+                }
+
+                state.loops += 1;
+                // constants defined in the body are defined again on the next iteration
+                let result = variables.scoped(|variables| {
+                    body.iter().try_for_each(|child| {
+                        interpret(child, depth + 1, variables, &specials, state).map(drop)
+                    })
+                });
+                state.loops -= 1;
+
+                // <continue /> only skips the rest of the body, the counter still has to be advanced
+                if let Err(err) = result {
+                    match err.downcast::<BlockControl>() {
+                        Err(e) => break 'outer Err(e),
+                        Ok(BlockControl::Break(value) | BlockControl::BreakLoop(value)) => {
+                            break 'outer Ok(value);
+                        }
+                        Ok(BlockControl::Continue) => {}
+                    }
+                }
+
+                iteration = match iteration.checked_add(step) {
+                    Some(next) => next,
+                    // the counter can't go past the end of the integer range, so the loop is over
+                    None => break 'outer Ok(Value::Null),
+                };
+            }?
+        }
+
+        name @ ("while" | "do-while") => {
+            let mut conditions = Vec::new();
+            let mut bodies = Vec::new();
+
+            for child in &element.children {
+                match child.name.to_lowercase().as_str() {
+                    "condition" => conditions.push(child),
+                    "do" => bodies.push(child),
+                    _ => bail!(RuntimeError::unexpected_child(name, &child.name)),
+                }
+            }
+
+            let ([condition], [body]) = (conditions.as_slice(), bodies.as_slice()) else {
+                bail!(RuntimeError::invalid_children(
+                    name,
+                    "exactly one <condition> and one <do> child"
+                ));
+            };
+
+            ensure!(
+                condition.children.len() == 1,
+                RuntimeError::invalid_children("condition", "exactly one child")
+            );
+
+            let mut iteration = 0i64;
+
+            'outer: loop {
+                let scope = HashMap::from([("iteration".to_string(), Value::Int(iteration))]);
+                let specials = specials.with(&scope);
+
+                // <do-while> checks its condition after the first iteration instead of before it
+                if (name == "while" || iteration > 0)
+                    && !interpret(
+                        &condition.children[0],
+                        depth + 2,
+                        variables,
+                        &specials,
+                        state,
+                    )?
+                    .as_bool()
+                {
+                    break 'outer Ok(Value::Null);
+                }
+
+                iteration += 1;
+
+                state.loops += 1;
+                let result = variables.scoped(|variables| {
+                    body.children.iter().try_for_each(|child| {
+                        interpret(child, depth + 2, variables, &specials, state).map(drop)
+                    })
+                });
+                state.loops -= 1;
+
+                if let Err(err) = result {
+                    match err.downcast::<BlockControl>() {
+                        Err(e) => break 'outer Err(e),
+                        Ok(BlockControl::Break(value) | BlockControl::BreakLoop(value)) => {
+                            break 'outer Ok(value);
+                        }
+                        Ok(BlockControl::Continue) => continue 'outer,
+                    }
+                }
+            }?
+        }
+
+        "function" => {
+            let name = element
+                .attributes
+                .get("name")
+                .ok_or_else(|| RuntimeError::missing_attribute("function", "name"))?
+                .clone();
+
+            ensure!(
+                !name.is_empty(),
+                RuntimeError::InvalidAttribute {
+                    element: "function".to_string(),
+                    attribute: "name".to_string(),
+                    value: name,
+                }
+            );
+
+            state.functions.insert(name, element.children.clone());
+
+            Value::Null
+        }
+
+        "call" => {
+            let name = element
+                .attributes
+                .get("name")
+                .ok_or_else(|| RuntimeError::missing_attribute("call", "name"))?;
+
+            call_function(
+                name,
+                element,
+                Some("name"),
+                depth,
+                variables,
+                specials,
+                state,
+            )?
+        }
+
+        "quote" => match element.children.as_slice() {
+            [] => bail!(RuntimeError::invalid_children(
+                "quote",
+                "at least one child"
+            )),
+            [child] => Value::Node(Box::new(child.clone())),
+            children => Value::List(
+                children
+                    .iter()
+                    .map(|child| Value::Node(Box::new(child.clone())))
+                    .collect(),
+            ),
+        },
+
+        "node" => {
+            let tag = element
+                .attributes
+                .get("tag")
+                .ok_or_else(|| RuntimeError::missing_attribute("node", "tag"))?;
+
+            let (prefix, name) = match tag.split_once(':') {
+                Some((prefix, name)) => (Some(prefix.to_string()), name),
+                None => (None, tag.as_str()),
+            };
+
+            ensure!(
+                !name.is_empty() && prefix.as_ref().is_none_or(|prefix| !prefix.is_empty()),
+                RuntimeError::InvalidAttribute {
+                    element: "node".to_string(),
+                    attribute: "tag".to_string(),
+                    value: tag.clone(),
+                }
+            );
+
+            let mut node = Element {
+                name: name.to_string(),
+                prefix,
+                attributes: element
+                    .attributes
+                    .iter()
+                    .filter(|(k, _)| k.as_str() != "tag")
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect(),
+                children: Vec::new(),
+                span: element.span,
+                expansions: Vec::new(),
+            };
+
+            for child in &element.children {
+                if child.name.to_lowercase() != "attribute" {
+                    let value = interpret(child, depth + 1, variables, specials, state)?;
+                    push_node_child(&mut node.children, value, element.span);
+                    continue;
+                }
+
+                let name = child
+                    .attributes
+                    .get("name")
+                    .ok_or_else(|| RuntimeError::missing_attribute("attribute", "name"))?;
+
+                ensure!(
+                    child.children.len() == 1,
+                    RuntimeError::invalid_children("attribute", "exactly one child")
+                );
+
+                let value = interpret(&child.children[0], depth + 1, variables, specials, state)?;
+                node.attributes.insert(name.clone(), value.to_string());
+            }
+
+            Value::Node(Box::new(node))
+        }
+
+        name @ ("node-name" | "node-attribute" | "node-attributes" | "node-children"
+        | "node-text") => {
+            ensure!(
+                element.children.len() == 1,
+                RuntimeError::invalid_children(name, "exactly one child")
+            );
+
+            let node = match interpret(&element.children[0], depth + 1, variables, specials, state)?
+            {
+                Value::Node(node) => node,
+                value => bail!(RuntimeError::IncompatibleTypes {
+                    operation: "inspect the node".to_string(),
+                    a: value,
+                    b: None,
+                }),
+            };
+
+            match name {
+                "node-name" => Value::Str(node.name.clone()),
+                "node-attribute" => {
+                    let attribute = element
+                        .attributes
+                        .get("name")
+                        .ok_or_else(|| RuntimeError::missing_attribute(name, "name"))?;
+
+                    match node.text() {
+                        Some(_) => Value::Null,
+                        None => node
+                            .attributes
+                            .get(attribute)
+                            .map_or(Value::Null, |value| Value::from(value.as_str())),
+                    }
+                }
+                "node-attributes" => {
+                    let mut names = match node.text() {
+                        Some(_) => Vec::new(),
+                        None => node.attributes.keys().cloned().collect::<Vec<_>>(),
+                    };
+                    names.sort();
+                    Value::List(names.into_iter().map(Value::Str).collect())
+                }
+                "node-children" => Value::List(
+                    node.children
+                        .into_iter()
+                        .map(|child| Value::Node(Box::new(child)))
+                        .collect(),
+                ),
+                _ => node.text().map_or(Value::Null, Value::from),
+            }
+        }
+
+        "eval" => {
+            ensure!(
+                element.children.len() == 1,
+                RuntimeError::invalid_children("eval", "exactly one child")
+            );
+
+            let fresh = match element.attributes.get("env").map(String::as_str) {
+                None | Some("current") => false,
+                Some("fresh") => true,
+                Some(env) => bail!(RuntimeError::InvalidAttribute {
+                    element: "eval".to_string(),
+                    attribute: "env".to_string(),
+                    value: env.to_string(),
+                }),
+            };
+
+            let code = match interpret(&element.children[0], depth + 1, variables, specials, state)?
+            {
+                Value::Node(node) => vec![*node],
+                Value::Str(source) => {
+                    let mut node = parse(&source).map_err(|err| {
+                        RuntimeError::InvalidXml(match err.downcast_ref::<ParseError>() {
+                            Some(ParseError::InvalidXml { message, .. }) => message.clone(),
+                            _ => err.to_string(),
+                        })
+                    })?;
+                    // parsed code has no place in the source file, so errors in it point at the <eval>
+                    node.relocate(element.span);
+                    vec![node]
+                }
+                Value::List(items) => items
+                    .into_iter()
+                    .map(|item| match item {
+                        Value::Node(node) => Ok(*node),
+                        item => Err(RuntimeError::IncompatibleTypes {
+                            operation: "evaluate".to_string(),
+                            a: item,
+                            b: None,
+                        }),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+                value => bail!(RuntimeError::IncompatibleTypes {
+                    operation: "evaluate".to_string(),
+                    a: value,
+                    b: None,
+                }),
+            };
+
+            // the code may define and use macros, which are expanded before it's checked like the program was
+            let mut program = Element {
+                name: "program".to_string(),
+                prefix: None,
+                attributes: HashMap::new(),
+                children: code,
+                span: element.span,
+                expansions: Vec::new(),
+            };
+            expand(&mut program, state)?;
+            check(&program, state)?;
+
+            // a whole program runs as a block of its children
+            let code = program
+                .children
+                .into_iter()
+                .flat_map(|node| {
+                    if node.name.to_lowercase() == "program" {
+                        node.children
+                    } else {
+                        vec![node]
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            let mut fresh_variables = Variables::default();
+            let fresh_specials = Specials::default();
+            let (variables, specials) = if fresh {
+                (&mut fresh_variables, &fresh_specials)
+            } else {
+                (variables, specials)
+            };
+            let ret = code.iter().try_fold(Value::Null, |_, child| {
+                interpret(child, depth + 1, variables, specials, state)
+            });
+
+            match ret {
+                Ok(val) => val,
+                Err(err) => match err.downcast::<BlockControl>() {
+                    Ok(BlockControl::Break(val)) => val,
+                    Ok(control) => return Err(control.into()),
                     Err(err) => return Err(err),
                 },
             }
         }
 
+        name @ ("struct" | "enum") => {
+            let type_name = element
+                .attributes
+                .get("name")
+                .ok_or_else(|| RuntimeError::missing_attribute(name, "name"))?
+                .clone();
+
+            ensure!(
+                !type_name.is_empty()
+                    && Value::of_type(&type_name).is_none()
+                    && type_name.to_lowercase() != "node",
+                RuntimeError::InvalidAttribute {
+                    element: name.to_string(),
+                    attribute: "name".to_string(),
+                    value: type_name,
+                }
+            );
+
+            if name == "struct" {
+                check_fields(element)?;
+
+                state.enums.remove(&type_name);
+                state.structs.insert(type_name, element.clone());
+            } else {
+                let mut variants = Vec::new();
+
+                for child in &element.children {
+                    ensure!(
+                        child.name.to_lowercase() == "variant",
+                        RuntimeError::unexpected_child("enum", &child.name)
+                    );
+
+                    let variant = child
+                        .attributes
+                        .get("name")
+                        .ok_or_else(|| RuntimeError::missing_attribute("variant", "name"))?;
+
+                    ensure!(
+                        !variants.contains(&variant),
+                        RuntimeError::invalid_children("enum", "variants with different names")
+                    );
+
+                    check_fields(child)?;
+                    variants.push(variant);
+                }
+
+                ensure!(
+                    !variants.is_empty(),
+                    RuntimeError::invalid_children("enum", "at least one <variant> child")
+                );
+
+                state.structs.remove(&type_name);
+                state.enums.insert(type_name, element.clone());
+            }
+
+            Value::Null
+        }
+
+        "new" => {
+            let name = element
+                .attributes
+                .get("type")
+                .ok_or_else(|| RuntimeError::missing_attribute("new", "type"))?
+                .clone();
+
+            // enums need a variant, which can't be used as the name of a field of theirs
+            let variant = if state.enums.contains_key(&name) {
+                Some(
+                    element
+                        .attributes
+                        .get("variant")
+                        .ok_or_else(|| RuntimeError::missing_attribute("new", "variant"))?
+                        .clone(),
+                )
+            } else {
+                None
+            };
+
+            let definition = definition(state, &name, variant.as_deref())?.clone();
+            let display_name = match &variant {
+                Some(variant) => format!("{name}.{variant}"),
+                None => name.clone(),
+            };
+
+            // field values from the attributes are strings, which are converted to the field's type below
+            let mut values: HashMap<String, Value> = element
+                .attributes
+                .iter()
+                .filter(|(attribute, _)| {
+                    attribute.as_str() != "type"
+                        && (variant.is_none() || attribute.as_str() != "variant")
+                })
+                .map(|(attribute, value)| (attribute.clone(), Value::from(value.as_str())))
+                .collect();
+
+            for child in &element.children {
+                ensure!(
+                    child.name.to_lowercase() == "field",
+                    RuntimeError::unexpected_child("new", &child.name)
+                );
+
+                let field = child
+                    .attributes
+                    .get("name")
+                    .ok_or_else(|| RuntimeError::missing_attribute("field", "name"))?;
+
+                ensure!(
+                    child.children.len() == 1,
+                    RuntimeError::invalid_children("field", "exactly one child")
+                );
+                ensure!(
+                    !values.contains_key(field),
+                    RuntimeError::invalid_children("new", "at most one value for each field")
+                );
+
+                let value = interpret(&child.children[0], depth + 2, variables, specials, state)?;
+                values.insert(field.clone(), value);
+            }
+
+            if let Some(field) = values.keys().find(|field| {
+                !definition
+                    .children
+                    .iter()
+                    .any(|child| child.attributes.get("name") == Some(*field))
+            }) {
+                bail!(RuntimeError::UndefinedField {
+                    name: display_name,
+                    field: field.clone(),
+                });
+            }
+
+            let mut fields = Vec::new();
+            for child in &definition.children {
+                let field = child.attributes.get("name").cloned().unwrap_or_default();
+
+                let value = match (values.remove(&field), child.children.first()) {
+                    (Some(value), _) => value,
+                    (None, Some(default)) => {
+                        interpret(default, depth + 1, variables, specials, state)?
+                    }
+                    (None, None) => bail!(RuntimeError::MissingField {
+                        name: display_name,
+                        field,
+                    }),
+                };
+
+                let value = field_value(&definition, &display_name, &field, value)?;
+                fields.push((field, value));
+            }
+
+            match variant {
+                Some(variant) => Value::Enum(Box::new(Variant {
+                    name,
+                    variant,
+                    fields,
+                })),
+                None => Value::Struct { name, fields },
+            }
+        }
+
+        "get-field" => {
+            let field = element
+                .attributes
+                .get("name")
+                .ok_or_else(|| RuntimeError::missing_attribute("get-field", "name"))?;
+
+            ensure!(
+                element.children.len() == 1,
+                RuntimeError::invalid_children("get-field", "exactly one child")
+            );
+
+            match interpret(&element.children[0], depth + 1, variables, specials, state)? {
+                Value::Struct { name, fields } => fields
+                    .into_iter()
+                    .find_map(|(f, value)| (f == *field).then_some(value))
+                    .ok_or_else(|| RuntimeError::UndefinedField {
+                        name,
+                        field: field.clone(),
+                    })?,
+                Value::Enum(variant) => variant
+                    .fields
+                    .into_iter()
+                    .find_map(|(f, value)| (f == *field).then_some(value))
+                    .ok_or_else(|| RuntimeError::UndefinedField {
+                        name: format!("{}.{}", variant.name, variant.variant),
+                        field: field.clone(),
+                    })?,
+                value => bail!(RuntimeError::IncompatibleTypes {
+                    operation: "get a field of".to_string(),
+                    a: value,
+                    b: None,
+                }),
+            }
+        }
+
+        "set-field" => {
+            let field = element
+                .attributes
+                .get("name")
+                .ok_or_else(|| RuntimeError::missing_attribute("set-field", "name"))?;
+
+            // with `var`, the struct in that variable is updated in place
+            let var = element.attributes.get("var");
+            let (target, value) = match (var, element.children.as_slice()) {
+                (Some(var), [value]) => (
+                    variables.get(var).cloned().unwrap_or_default(),
+                    interpret(value, depth + 1, variables, specials, state)?,
+                ),
+                (None, [target, value]) => (
+                    interpret(target, depth + 1, variables, specials, state)?,
+                    interpret(value, depth + 1, variables, specials, state)?,
+                ),
+                (Some(_), _) => bail!(RuntimeError::invalid_children(
+                    "set-field",
+                    "exactly one child when the `var` attribute is provided"
+                )),
+                (None, _) => bail!(RuntimeError::invalid_children(
+                    "set-field",
+                    "exactly 2 children"
+                )),
+            };
+
+            let (name, variant, mut fields) = match target {
+                Value::Struct { name, fields } => (name, None, fields),
+                Value::Enum(variant) => (variant.name, Some(variant.variant), variant.fields),
+                target => bail!(RuntimeError::IncompatibleTypes {
+                    operation: "set a field of".to_string(),
+                    a: target,
+                    b: None,
+                }),
+            };
+
+            let display_name = match &variant {
+                Some(variant) => format!("{name}.{variant}"),
+                None => name.clone(),
+            };
+
+            let value = field_value(
+                definition(state, &name, variant.as_deref())?,
+                &display_name,
+                field,
+                value,
+            )?;
+            let slot = fields
+                .iter_mut()
+                .find_map(|(f, slot)| (f == field).then_some(slot))
+                .ok_or_else(|| RuntimeError::UndefinedField {
+                    name: display_name,
+                    field: field.clone(),
+                })?;
+            *slot = value;
+
+            let updated = match variant {
+                Some(variant) => Value::Enum(Box::new(Variant {
+                    name,
+                    variant,
+                    fields,
+                })),
+                None => Value::Struct { name, fields },
+            };
+            if let Some(var) = var {
+                variables.set(var, updated.clone())?;
+            }
+
+            updated
+        }
+
         "rand" => {
             ensure!(
                 element.children.is_empty(),
-                "Expected no children in <rand> element"
+                RuntimeError::invalid_children("rand", "no children")
             );
 
-            let min = element
+            let float = element
                 .attributes
-                .get("min")
-                .and_then(|s| s.parse::<i64>().ok())
-                .unwrap_or(0);
+                .get("type")
+                .is_some_and(|s| s.to_lowercase() == "float");
 
-            let max = element
-                .attributes
-                .get("max")
-                .and_then(|s| s.parse::<i64>().ok())
-                .unwrap_or(i64::MAX);
+            if float {
+                let min = number_attribute::<f64>(element, "min")?.unwrap_or(0.0);
+
+                let max = number_attribute::<f64>(element, "max")?.unwrap_or(1.0);
+
+                if min >= max {
+                    bail!(RuntimeError::InvalidRange("rand".to_string()));
+                }
+
+                Value::Float(state.rng.random_range(min..max))
+            } else {
+                let min = number_attribute::<i64>(element, "min")?.unwrap_or(0);
+
+                let max = number_attribute::<i64>(element, "max")?.unwrap_or(i64::MAX);
 
-            if min >= max {
-                bail!("`min` must be less than `max` in <rand> element");
+                if min >= max {
+                    bail!(RuntimeError::InvalidRange("rand".to_string()));
+                }
+
+                Value::Int(state.rng.random_range(min..max))
             }
+        }
+
+        "seed" => {
+            let seed = if let Some(seed) = number_attribute::<u64>(element, "value")? {
+                seed
+            } else {
+                ensure!(
+                    element.children.len() == 1,
+                    RuntimeError::invalid_children(
+                        "seed",
+                        "exactly one child or the `value` attribute"
+                    )
+                );
+
+                let child = &element.children[0];
+                let value = interpret(child, depth + 1, variables, specials, state)?;
+
+                value
+                    .as_int()
+                    .and_then(|seed| u64::try_from(seed).ok())
+                    .ok_or_else(|| {
+                        RuntimeError::conversion_failed(&value, "a non-negative integer")
+                    })?
+            };
+
+            state.reseed(seed);
+
+            Value::Null
+        }
+
+        "choice" => {
+            let child = element
+                .children
+                .choose(&mut state.rng)
+                .ok_or_else(|| RuntimeError::invalid_children("choice", "at least one child"))?;
+
+            interpret(child, depth + 1, variables, specials, state)?
+        }
+
+        "shuffle" => {
+            let values = element
+                .children
+                .iter()
+                .map(|child| interpret(child, depth + 1, variables, specials, state))
+                .collect::<Result<Vec<Value>>>()?;
+
+            let mut items = match <[Value; 1]>::try_from(values) {
+                Ok([Value::List(items)]) => items,
+                Ok([value]) => vec![value],
+                Err(values) => values,
+            };
+
+            items.shuffle(&mut state.rng);
 
-            let random_value = rand::random_range(min..max);
-            Value::Int(random_value)
+            Value::List(items)
         }
 
-        _ => bail!("Unknown element: {}", element.name),
+        _ if state.functions.contains_key(&element.name) => call_function(
+            &element.name,
+            element,
+            None,
+            depth,
+            variables,
+            specials,
+            state,
+        )?,
+
+        _ => bail!(RuntimeError::UnknownElement(element.name.clone())),
     })
 }
@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+use miette::Diagnostic;
+use thiserror::Error;
+
+// how deep `<call>`s can nest before we assume the recursion is unbounded
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
+
+const STACK_RED_ZONE: usize = 256 * 1024;
+const STACK_GROWTH: usize = 4 * 1024 * 1024;
+
+// grows the stack on the heap instead of overflowing it, for every function that recurses into the children of an element,
+// since nothing limits how deeply a program can nest its elements
+pub fn grow_stack<R>(f: impl FnOnce() -> R) -> R {
+    stacker::maybe_grow(STACK_RED_ZONE, STACK_GROWTH, f)
+}
+
+#[derive(Debug, Clone)]
+pub struct Limits {
+    pub max_steps: Option<u64>,
+    pub max_call_depth: Option<usize>,
+    pub timeout: Option<Duration>,
+    pub max_string_length: Option<usize>,
+    // whether `<try>` is allowed to catch a `LimitExceeded` error
+    pub catchable: bool,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_steps: None,
+            max_call_depth: Some(DEFAULT_MAX_CALL_DEPTH),
+            timeout: None,
+            max_string_length: None,
+            catchable: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Error, Diagnostic)]
+pub enum LimitExceeded {
+    #[error("Exceeded the maximum number of evaluation steps ({0})")]
+    #[diagnostic(
+        code(xmlang::limit_exceeded::steps),
+        help("Check for infinite loops, or raise the limit with `--max-steps`")
+    )]
+    Steps(u64),
+
+    #[error("Exceeded the maximum call depth ({0})")]
+    #[diagnostic(
+        code(xmlang::limit_exceeded::call_depth),
+        help("Check for unbounded recursion, or raise the limit with `--max-call-depth`")
+    )]
+    CallDepth(usize),
+
+    #[error("Exceeded the maximum execution time ({0:?})")]
+    #[diagnostic(
+        code(xmlang::limit_exceeded::timeout),
+        help("Check for infinite loops, or raise the limit with `--timeout`")
+    )]
+    Timeout(Duration),
+
+    #[error("Exceeded the maximum string length ({0} bytes)")]
+    #[diagnostic(
+        code(xmlang::limit_exceeded::string_length),
+        help("Raise the limit with `--max-string-length`")
+    )]
+    StringLength(usize),
+}
+
+impl LimitExceeded {
+    // running out of steps or time can't be caught, even with `--catchable-limits`, since the <catch> handling it
+    // would run out of them again right away
+    pub fn is_catchable(&self) -> bool {
+        matches!(self, Self::CallDepth(_) | Self::StringLength(_))
+    }
+}
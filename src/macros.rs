@@ -0,0 +1,343 @@
+use std::{
+    collections::{HashMap, HashSet},
+    mem,
+};
+
+use miette::Result;
+
+use crate::{
+    element::{Element, Expansion},
+    error::RuntimeError,
+    limits::grow_stack,
+    state::State,
+};
+
+// how deeply macros can be used inside the bodies of other macros, which catches macros that use themselves
+const MAX_EXPANSION_DEPTH: usize = 64;
+
+// names that can't be given to macros, since expanding them would break macros themselves
+const RESERVED_NAMES: [&str; 3] = ["macro", "slot", "quote"];
+
+// collects the <macro> definitions among the children of the element, then replaces every use of a macro with its body
+//
+// this happens before the program is checked and run, so macros can be used anywhere, even before they're defined
+pub fn expand(element: &mut Element, state: &mut State) -> Result<()> {
+    collect_macros(element, state)?;
+    element.children = expand_all(mem::take(&mut element.children), state, 0)?;
+
+    Ok(())
+}
+
+// moves the <macro> elements out of the tree and into the state, skipping quoted code
+fn collect_macros(element: &mut Element, state: &mut State) -> Result<()> {
+    for mut child in mem::take(&mut element.children) {
+        match child.name.to_lowercase().as_str() {
+            "macro" => define(child, state)?,
+            "quote" => element.children.push(child),
+            _ => {
+                grow_stack(|| collect_macros(&mut child, state))?;
+                element.children.push(child);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn define(mut definition: Element, state: &mut State) -> Result<()> {
+    let name = definition
+        .attributes
+        .get("name")
+        .ok_or_else(|| {
+            RuntimeError::locate(
+                RuntimeError::missing_attribute("macro", "name").into(),
+                definition.span,
+            )
+        })?
+        .clone();
+
+    if name.is_empty() || RESERVED_NAMES.contains(&name.to_lowercase().as_str()) {
+        return Err(RuntimeError::locate(
+            RuntimeError::InvalidAttribute {
+                element: "macro".to_string(),
+                attribute: "name".to_string(),
+                value: name,
+            }
+            .into(),
+            definition.span,
+        ));
+    }
+
+    // macros defined inside other macros are still defined everywhere
+    collect_macros(&mut definition, state)?;
+    state.macros.insert(name.to_lowercase(), definition);
+
+    Ok(())
+}
+
+fn expand_all(elements: Vec<Element>, state: &mut State, depth: usize) -> Result<Vec<Element>> {
+    let mut expanded = Vec::with_capacity(elements.len());
+
+    for mut element in elements {
+        let name = element.name.to_lowercase();
+
+        if name != "quote" {
+            element.children =
+                grow_stack(|| expand_all(mem::take(&mut element.children), state, depth))?;
+        }
+
+        // elements with the function prefix always call functions, even if a macro has the same name
+        let definition = if element.prefix.as_ref() == Some(&state.function_prefix) {
+            None
+        } else {
+            state.macros.get(&name).cloned()
+        };
+
+        match definition {
+            Some(definition) => expanded.extend(expand_use(&definition, element, state, depth)?),
+            None => expanded.push(element),
+        }
+    }
+
+    Ok(expanded)
+}
+
+fn expand_use(
+    definition: &Element,
+    use_site: Element,
+    state: &mut State,
+    depth: usize,
+) -> Result<Vec<Element>> {
+    if depth >= MAX_EXPANSION_DEPTH {
+        return Err(RuntimeError::locate(
+            RuntimeError::MacroRecursion(use_site.name.clone()).into(),
+            use_site.span,
+        ));
+    }
+
+    let expansion = Expansion {
+        name: use_site.name.clone(),
+        span: use_site.span,
+    };
+
+    state.expanded += 1;
+    let body = Instance::new(definition, &use_site, state.expanded)
+        .and_then(|instance| instance.substitute(&definition.children, false))
+        .map_err(|err| RuntimeError::locate(err.into(), use_site.span))?;
+
+    // the body may use other macros, including ones in the slots
+    let mut body = expand_all(body, state, depth + 1)
+        .map_err(|err| RuntimeError::expanded(err, std::slice::from_ref(&expansion)))?;
+
+    for element in &mut body {
+        element.expansions.push(expansion.clone());
+        element
+            .expansions
+            .extend(use_site.expansions.iter().cloned());
+    }
+
+    Ok(body)
+}
+
+// a single use of a macro, with what it gives to each slot
+struct Instance<'a> {
+    use_site: &'a Element,
+    // the children of the use that fill named slots, by the name of the slot
+    fillers: HashMap<&'a str, &'a Element>,
+    // the other children of the use, which fill the unnamed slot
+    rest: Vec<&'a Element>,
+    // the variables the macro introduces, and their names in this expansion
+    renames: HashMap<String, String>,
+}
+
+impl<'a> Instance<'a> {
+    fn new(definition: &Element, use_site: &'a Element, id: u64) -> Result<Self, RuntimeError> {
+        let mut slots = HashSet::new();
+        let has_unnamed_slot = collect_slots(&definition.children, &mut slots);
+
+        if let Some((attribute, value)) = use_site
+            .attributes
+            .iter()
+            .find(|(attribute, _)| !slots.contains(attribute.as_str()))
+        {
+            return Err(RuntimeError::InvalidAttribute {
+                element: use_site.name.clone(),
+                attribute: attribute.clone(),
+                value: value.clone(),
+            });
+        }
+
+        let mut fillers = HashMap::new();
+        let mut rest = Vec::new();
+
+        for child in &use_site.children {
+            if slots.contains(child.name.as_str()) {
+                if fillers.insert(child.name.as_str(), child).is_some() {
+                    return Err(RuntimeError::unexpected_child(&use_site.name, &child.name));
+                }
+            } else if has_unnamed_slot {
+                rest.push(child);
+            } else {
+                return Err(RuntimeError::unexpected_child(
+                    &use_site.name,
+                    child.text().unwrap_or(&child.name),
+                ));
+            }
+        }
+
+        // variables are renamed in every expansion, so they never clash with the ones of the code using the macro
+        let mut introduced = HashSet::new();
+        collect_introduced(&definition.children, &mut HashSet::new(), &mut introduced);
+        let renames = introduced
+            .into_iter()
+            .map(|name| {
+                let renamed = format!("{name}#{id}");
+                (name, renamed)
+            })
+            .collect();
+
+        Ok(Self {
+            use_site,
+            fillers,
+            rest,
+            renames,
+        })
+    }
+
+    // copies the body of the macro, replacing its slots with what the use gives them
+    fn substitute(
+        &self,
+        template: &[Element],
+        in_format: bool,
+    ) -> Result<Vec<Element>, RuntimeError> {
+        let mut output = Vec::new();
+
+        for element in template {
+            if element.name.to_lowercase() != "slot" {
+                let mut copy = Element {
+                    name: element.name.clone(),
+                    prefix: element.prefix.clone(),
+                    attributes: element.attributes.clone(),
+                    children: grow_stack(|| {
+                        self.substitute(&element.children, element.name.to_lowercase() == "format")
+                    })?,
+                    span: element.span,
+                    expansions: element.expansions.clone(),
+                };
+
+                if let Some(var) = copy.attributes.get_mut("var")
+                    && let Some(renamed) = self.renames.get(var)
+                {
+                    *var = renamed.clone();
+                }
+
+                if in_format && let Some(text) = copy.attributes.get_mut("_text") {
+                    *text = self.rename_placeholders(text);
+                }
+
+                output.push(copy);
+                continue;
+            }
+
+            let Some(slot) = element.attributes.get("name") else {
+                output.extend(self.rest.iter().map(|&child| child.clone()));
+                continue;
+            };
+
+            if let Some(filler) = self.fillers.get(slot.as_str()) {
+                output.extend(filler.children.iter().cloned());
+            } else if let Some(value) = self.use_site.attributes.get(slot) {
+                output.push(Element {
+                    name: String::new(),
+                    prefix: None,
+                    attributes: HashMap::from([("_text".to_string(), value.clone())]),
+                    children: Vec::new(),
+                    span: self.use_site.span,
+                    expansions: Vec::new(),
+                });
+            } else if !element.children.is_empty() {
+                output.extend(self.substitute(&element.children, in_format)?);
+            } else {
+                return Err(RuntimeError::MissingSlot {
+                    name: self.use_site.name.clone(),
+                    slot: slot.clone(),
+                });
+            }
+        }
+
+        Ok(output)
+    }
+
+    // renames the renamed variables in the placeholders of a <format> template
+    fn rename_placeholders(&self, text: &str) -> String {
+        self.renames
+            .iter()
+            .fold(text.to_string(), |text, (name, renamed)| {
+                text.replace(&format!("{{{name}}}"), &format!("{{{renamed}}}"))
+                    .replace(&format!("{{{name}:"), &format!("{{{renamed}:"))
+            })
+    }
+}
+
+// collects the names of the named slots, and returns whether there's an unnamed one
+fn collect_slots<'a>(template: &'a [Element], slots: &mut HashSet<&'a str>) -> bool {
+    let mut has_unnamed = false;
+
+    for element in template {
+        if element.name.to_lowercase() == "slot" {
+            match element.attributes.get("name") {
+                Some(name) => {
+                    slots.insert(name);
+                }
+                None => has_unnamed = true,
+            }
+        }
+
+        has_unnamed |= grow_stack(|| collect_slots(&element.children, slots));
+    }
+
+    has_unnamed
+}
+
+// collects the variables the macro introduces: the ones it assigns to before using them in any other way,
+// in the order the elements run, so the ones it reads or updates are left to the code using the macro
+fn collect_introduced(
+    template: &[Element],
+    seen: &mut HashSet<String>,
+    introduced: &mut HashSet<String>,
+) {
+    for element in template {
+        let var = element.attributes.get("var");
+
+        match element.name.to_lowercase().as_str() {
+            // the loop variable is set before the body runs
+            "loop" => {
+                if let Some(var) = var
+                    && seen.insert(var.clone())
+                {
+                    introduced.insert(var.clone());
+                }
+                grow_stack(|| collect_introduced(&element.children, seen, introduced));
+            }
+
+            // the value is evaluated before it's assigned
+            "set" | "const" => {
+                grow_stack(|| collect_introduced(&element.children, seen, introduced));
+                if let Some(var) = var
+                    && seen.insert(var.clone())
+                {
+                    introduced.insert(var.clone());
+                }
+            }
+
+            // everything else, like <get>, <inc /> or <set-field>, which updates the struct already in the
+            // variable, uses it
+            _ => {
+                if let Some(var) = var {
+                    seen.insert(var.clone());
+                }
+                grow_stack(|| collect_introduced(&element.children, seen, introduced));
+            }
+        }
+    }
+}
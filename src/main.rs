@@ -1,27 +1,112 @@
-use std::{collections::HashMap, env::args, path::Path};
+use std::{fs, path::PathBuf, time::Duration};
 
-use miette::Result;
+use clap::Parser;
+use miette::{Context, IntoDiagnostic, NamedSource, Result};
 
-use crate::{interpreter::interpret, parser::parse};
+use crate::{
+    check::check,
+    interpreter::interpret,
+    limits::{DEFAULT_MAX_CALL_DEPTH, Limits},
+    macros::expand,
+    parser::parse,
+    specials::Specials,
+    stack::StackTrace,
+    state::State,
+    value::Overflow,
+    variables::Variables,
+};
 
+mod check;
 mod element;
+mod error;
+mod format;
 mod interpreter;
+mod limits;
+mod macros;
 mod parser;
+mod specials;
+mod stack;
+mod state;
 mod value;
+mod variables;
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Args {
+    /// Path to the XML file to run
+    path: PathBuf,
+
+    /// Seed for the random number generator, making random elements reproducible
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Maximum number of elements to evaluate before aborting
+    #[arg(long)]
+    max_steps: Option<u64>,
+
+    /// Maximum nesting depth of function calls
+    #[arg(long, default_value_t = DEFAULT_MAX_CALL_DEPTH)]
+    max_call_depth: usize,
+
+    /// Maximum execution time in milliseconds
+    #[arg(long)]
+    timeout: Option<u64>,
+
+    /// Maximum length of any string value in bytes
+    #[arg(long)]
+    max_string_length: Option<usize>,
+
+    /// Allow `<try>` to catch errors caused by exceeding the limits above
+    #[arg(long)]
+    catchable_limits: bool,
+
+    /// What integer arithmetic does on overflow: `checked`, `wrapping`, `saturating` or `promote`
+    #[arg(long, default_value = "checked")]
+    overflow: Overflow,
+}
 
 fn main() -> Result<()> {
     miette::set_panic_hook();
 
-    // TODO: replace this with clap once we need more complex argument parsing
-    let path = args()
-        .nth(1)
-        .expect("Provide the path to the XML file as the first command line argument");
+    let args = Args::parse();
+
+    let source = fs::read_to_string(&args.path)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Failed to read `{}`", args.path.display()))?;
+
+    let named_source = || NamedSource::new(args.path.display().to_string(), source.clone());
+
+    let mut tree = parse(&source).map_err(|err| err.with_source_code(named_source()))?;
+
+    let mut state = State::new(args.seed).with_limits(Limits {
+        max_steps: args.max_steps,
+        max_call_depth: Some(args.max_call_depth),
+        timeout: args.timeout.map(Duration::from_millis),
+        max_string_length: args.max_string_length,
+        catchable: args.catchable_limits,
+    });
+    state.overflow = args.overflow;
+    if let Some(prefix) = tree.attributes.get("function-prefix") {
+        state.function_prefix = prefix.clone();
+    }
 
-    let path = Path::new(&path);
+    expand(&mut tree, &mut state).map_err(|err| err.with_source_code(named_source()))?;
+    check(&tree, &mut state).map_err(|err| err.with_source_code(named_source()))?;
 
-    let tree = parse(path)?;
+    if let Err(err) = interpret(
+        &tree,
+        0,
+        &mut Variables::default(),
+        &Specials::default(),
+        &mut state,
+    ) {
+        let err = match state.trace.take() {
+            Some(frames) if !frames.is_empty() => StackTrace::new(err, &frames).into(),
+            _ => err,
+        };
 
-    interpret(&tree, 0, &mut HashMap::new(), &[], &mut HashMap::new())?;
+        return Err(err.with_source_code(named_source()));
+    }
 
     Ok(())
 }
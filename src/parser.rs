@@ -1,53 +1,231 @@
-use std::{collections::HashMap, path::Path};
+use std::collections::HashMap;
 
-use miette::{IntoDiagnostic, Result, bail};
+use miette::{Diagnostic, IntoDiagnostic, Result, SourceSpan};
 use quick_xml::{
     Decoder, Reader,
+    escape::resolve_predefined_entity,
     events::{BytesStart, Event},
 };
+use thiserror::Error;
 
 use crate::element::Element;
 
+#[derive(Debug, Error, Diagnostic)]
+pub enum ParseError {
+    #[error("Invalid XML: {message}")]
+    #[diagnostic(code(xmlang::invalid_xml))]
+    InvalidXml {
+        message: String,
+        #[label]
+        span: SourceSpan,
+    },
+
+    #[error("Unknown entity reference: &{name};")]
+    #[diagnostic(
+        code(xmlang::unknown_entity),
+        help(
+            "Only the predefined entities (&lt; &gt; &amp; &apos; &quot;) and character references (like &#60;) are supported"
+        )
+    )]
+    UnknownEntity {
+        name: String,
+        #[label]
+        span: SourceSpan,
+    },
+
+    #[error("No root element found")]
+    #[diagnostic(code(xmlang::no_root))]
+    NoRoot,
+}
+
 impl Element {
-    fn from_event(e: BytesStart, decoder: &Decoder) -> Result<Self> {
+    fn from_event(e: BytesStart, decoder: &Decoder, span: SourceSpan) -> Result<Self> {
         let name = e.name().local_name();
         let name = decoder.decode(name.as_ref()).into_diagnostic()?;
+        let prefix = match e.name().prefix() {
+            Some(prefix) => Some(
+                decoder
+                    .decode(prefix.as_ref())
+                    .into_diagnostic()?
+                    .to_string(),
+            ),
+            None => None,
+        };
 
         let attributes = e
             .attributes()
             .map(|attr| {
-                // TODO: include a code snippet
-                let attr = attr.into_diagnostic()?;
+                let attr = attr.map_err(|err| ParseError::InvalidXml {
+                    message: err.to_string(),
+                    span,
+                })?;
                 let key = decoder.decode(attr.key.as_ref()).into_diagnostic()?;
-                let value = decoder.decode(attr.value.as_ref()).into_diagnostic()?;
+                let value = attr.decode_and_unescape_value(*decoder).map_err(|err| {
+                    ParseError::InvalidXml {
+                        message: err.to_string(),
+                        span,
+                    }
+                })?;
                 Ok((key.to_string(), value.to_string()))
             })
             .collect::<Result<HashMap<_, _>>>()?;
 
         Ok(Self {
             name: name.to_string(),
+            prefix,
             attributes,
             children: Vec::new(),
+            span,
+            expansions: Vec::new(),
         })
     }
 }
 
-pub fn parse(path: &Path) -> Result<Element> {
-    let mut reader = Reader::from_file(path).into_diagnostic()?;
-    reader.config_mut().trim_text(true);
-    let mut buf = Vec::new();
+// the text, references and CDATA sections between two tags, which together form one text node
+#[derive(Debug, Default)]
+struct PendingText {
+    // CDATA sections and references are literal, so they're never trimmed
+    pieces: Vec<(String, bool)>,
+    start: Option<u64>,
+    end: u64,
+}
+
+impl PendingText {
+    fn push(&mut self, text: String, literal: bool, start: u64, end: u64) {
+        self.pieces.push((text, literal));
+        self.start.get_or_insert(start);
+        self.end = end;
+    }
+
+    // builds the text node, or returns `None` if there's nothing but whitespace
+    //
+    // when preserving whitespace, whitespace-only text is kept in elements that join their children into a string,
+    // unless it's indentation (whitespace spanning multiple lines)
+    fn take(
+        &mut self,
+        source: &str,
+        preserve: bool,
+        parent: Option<&Element>,
+        before_child: bool,
+    ) -> Option<Element> {
+        let start = self.start.take()?;
+        let mut pieces = std::mem::take(&mut self.pieces);
+
+        let text_content = parent.is_some_and(takes_text_content);
+        // the template of <format> is the text around its other children, so the spaces next to them are part of it
+        let inline = parent.is_some_and(|parent| {
+            parent.prefix.is_none() && parent.name.eq_ignore_ascii_case("format")
+        });
+        let after_child = parent.is_some_and(|parent| !parent.children.is_empty());
+
+        if !preserve {
+            if let Some((text, false)) = pieces.first_mut() {
+                let trimmed = text.trim_start();
+                if !(inline && after_child && is_inline(&text[..text.len() - trimmed.len()])) {
+                    *text = trimmed.to_string();
+                }
+            }
+            if let Some((text, false)) = pieces.last_mut() {
+                let trimmed = text.trim_end();
+                if !(inline && before_child && is_inline(&text[trimmed.len()..])) {
+                    *text = trimmed.to_string();
+                }
+            }
+        }
+
+        let has_literal = pieces.iter().any(|(_, literal)| *literal);
+        let text = pieces.into_iter().map(|(text, _)| text).collect::<String>();
+
+        if !has_literal
+            && text.trim().is_empty()
+            && (!preserve || !text_content || text.is_empty() || text.contains('\n'))
+        {
+            return None;
+        }
+
+        Some(Element {
+            name: String::new(),
+            prefix: None,
+            attributes: HashMap::from([("_text".to_string(), text)]),
+            children: Vec::new(),
+            span: span(source, start, self.end),
+            expansions: Vec::new(),
+        })
+    }
+}
+
+// the elements that join their children into a string, so whitespace between the children is part of the string
+const TEXT_CONTENT_ELEMENTS: [&str; 5] = ["print", "string", "str", "format", "throw"];
+
+fn takes_text_content(element: &Element) -> bool {
+    element.prefix.is_none()
+        && TEXT_CONTENT_ELEMENTS.contains(&element.name.to_lowercase().as_str())
+}
+
+// whitespace on a single line, as opposed to indentation
+fn is_inline(whitespace: &str) -> bool {
+    !whitespace.contains('\n')
+}
+
+// the span of the source between two reader positions, without the surrounding whitespace
+fn span(source: &str, start: u64, end: u64) -> SourceSpan {
+    let (start, end) = (start as usize, end as usize);
+    let text = &source[start..end];
+
+    let leading = text.len() - text.trim_start().len();
+    let trailing = text.len() - text.trim_end().len();
+
+    (start + leading..(end - trailing).max(start + leading)).into()
+}
+
+pub fn parse(source: &str) -> Result<Element> {
+    let mut reader = Reader::from_str(source);
     let decoder = reader.decoder();
 
     let mut stack: Vec<Element> = Vec::new();
+    let mut text = PendingText::default();
+    // whether each element in the stack preserves whitespace, as set by `xml:space`
+    let mut preserve: Vec<bool> = Vec::new();
 
     loop {
-        match reader.read_event_into(&mut buf) {
+        let start = reader.buffer_position();
+        let event = reader.read_event();
+        let end = reader.buffer_position();
+        let span = span(source, start, end);
+
+        // any tag ends the text node before it
+        if matches!(
+            event,
+            Ok(Event::Start(_) | Event::Empty(_) | Event::End(_) | Event::Eof)
+        ) && let Some(element) = text.take(
+            source,
+            preserve.last().copied().unwrap_or(false),
+            stack.last(),
+            matches!(event, Ok(Event::Start(_) | Event::Empty(_))),
+        ) {
+            if let Some(parent) = stack.last_mut() {
+                parent.children.push(element);
+            } else {
+                return Ok(element);
+            }
+        }
+
+        match event {
             Ok(Event::Start(e)) => {
-                stack.push(Element::from_event(e, &decoder)?);
+                let element = Element::from_event(e, &decoder, span)?;
+
+                preserve.push(
+                    match element.attributes.get("xml:space").map(String::as_str) {
+                        Some("preserve") => true,
+                        Some("default") => false,
+                        _ => preserve.last().copied().unwrap_or(false),
+                    },
+                );
+                stack.push(element);
             }
 
             Ok(Event::Empty(e)) => {
-                let element = Element::from_event(e, &decoder)?;
+                let element = Element::from_event(e, &decoder, span)?;
 
                 if let Some(parent) = stack.last_mut() {
                     parent.children.push(element);
@@ -57,24 +235,43 @@ pub fn parse(path: &Path) -> Result<Element> {
             }
 
             Ok(Event::Text(e)) => {
-                let element = Element {
-                    name: String::new(),
-                    attributes: HashMap::from([(
-                        "_text".to_string(),
-                        decoder.decode(e.as_ref()).into_diagnostic()?.to_string(),
-                    )]),
-                    children: Vec::new(),
+                let content = e.decode().into_diagnostic()?;
+                text.push(content.into_owned(), false, start, end);
+            }
+
+            Ok(Event::CData(e)) => {
+                let content = e.decode().into_diagnostic()?;
+                text.push(content.into_owned(), true, start, end);
+            }
+
+            Ok(Event::GeneralRef(e)) => {
+                let resolved = match e.resolve_char_ref() {
+                    Ok(Some(ch)) => ch.to_string(),
+                    Ok(None) => {
+                        let name = e.decode().into_diagnostic()?;
+
+                        resolve_predefined_entity(&name)
+                            .ok_or_else(|| ParseError::UnknownEntity {
+                                name: name.to_string(),
+                                span,
+                            })?
+                            .to_string()
+                    }
+                    Err(err) => {
+                        return Err(ParseError::InvalidXml {
+                            message: err.to_string(),
+                            span,
+                        }
+                        .into());
+                    }
                 };
 
-                if let Some(parent) = stack.last_mut() {
-                    parent.children.push(element);
-                } else {
-                    return Ok(element);
-                }
+                text.push(resolved, true, start, end);
             }
 
             Ok(Event::End(_)) => {
                 let completed_element = stack.pop().unwrap();
+                preserve.pop();
 
                 if let Some(parent) = stack.last_mut() {
                     parent.children.push(completed_element);
@@ -85,16 +282,17 @@ pub fn parse(path: &Path) -> Result<Element> {
 
             Ok(Event::Eof) => break,
 
-            Ok(Event::Comment(_)) => {}
-
-            // TODO: include a code snippet
-            Err(e) => bail!("Error at position {}: {:?}", reader.error_position(), e),
+            // comments, the XML declaration, processing instructions and doctypes don't affect the program
+            Ok(Event::Comment(_) | Event::Decl(_) | Event::PI(_) | Event::DocType(_)) => {}
 
-            ev => {
-                println!("Other event: {ev:?}");
+            Err(e) => {
+                return Err(ParseError::InvalidXml {
+                    message: e.to_string(),
+                    span: (reader.error_position() as usize, 0).into(),
+                }
+                .into());
             }
         }
-        buf.clear();
     }
 
     while stack.len() > 1 {
@@ -110,6 +308,6 @@ pub fn parse(path: &Path) -> Result<Element> {
     if let Some(root) = stack.pop() {
         Ok(root)
     } else {
-        bail!("No root element found")
+        Err(ParseError::NoRoot.into())
     }
 }
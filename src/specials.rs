@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+
+use crate::value::Value;
+
+// the specials an element can see: the ones of the innermost scope first, then the ones of the scopes around it
+//
+// each scope only links to the scopes around it instead of copying them, so entering a scope, like calling a function,
+// takes the same time however deep it's nested
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Specials<'a> {
+    scope: Option<(&'a HashMap<String, Value>, &'a Specials<'a>)>,
+}
+
+impl<'a> Specials<'a> {
+    // a new innermost scope, whose specials hide the ones with the same name in the scopes around it
+    pub fn with<'b>(&'b self, scope: &'b HashMap<String, Value>) -> Specials<'b> {
+        Specials {
+            scope: Some((scope, self)),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&'a Value> {
+        let mut specials = self;
+
+        while let Some((scope, outer)) = specials.scope {
+            if let Some(value) = scope.get(name) {
+                return Some(value);
+            }
+            specials = outer;
+        }
+
+        None
+    }
+}
@@ -0,0 +1,126 @@
+use std::{
+    error::Error,
+    fmt::{Display, Formatter},
+};
+
+use miette::{Diagnostic, LabeledSpan, Report, Severity, SourceCode, SourceSpan};
+
+use crate::value::Value;
+
+// frames at each end of the stack that are shown when printing a very deep stack trace
+const SHOWN_FRAMES: usize = 8;
+
+#[derive(Debug, Clone)]
+pub struct CallFrame {
+    pub name: String,
+    pub span: SourceSpan,
+    pub attributes: Vec<(String, Value)>,
+    pub children: Vec<Value>,
+}
+
+impl Display for CallFrame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let arguments = self
+            .attributes
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .chain(self.children.iter().map(|value| value.to_string()))
+            .collect::<Vec<_>>();
+
+        write!(f, "{}({})", self.name, arguments.join(", "))
+    }
+}
+
+#[derive(Debug, Clone, thiserror::Error, Diagnostic)]
+enum FrameDiagnostic {
+    #[error("in {frame}")]
+    #[diagnostic(severity(Advice))]
+    Call {
+        frame: CallFrame,
+        #[label("called here")]
+        span: SourceSpan,
+    },
+
+    #[error("... {0} more calls")]
+    #[diagnostic(severity(Advice))]
+    Omitted(usize),
+}
+
+// an uncaught error, together with the calls that led to it
+#[derive(Debug)]
+pub struct StackTrace {
+    error: Report,
+    frames: Vec<FrameDiagnostic>,
+}
+
+impl StackTrace {
+    // `frames` are ordered from the outermost call to the innermost one
+    pub fn new(error: Report, frames: &[CallFrame]) -> Self {
+        let frame = |frame: &CallFrame| FrameDiagnostic::Call {
+            frame: frame.clone(),
+            span: frame.span,
+        };
+
+        let frames = if frames.len() > SHOWN_FRAMES * 2 {
+            frames[frames.len() - SHOWN_FRAMES..]
+                .iter()
+                .rev()
+                .map(frame)
+                .chain([FrameDiagnostic::Omitted(frames.len() - SHOWN_FRAMES * 2)])
+                .chain(frames[..SHOWN_FRAMES].iter().rev().map(frame))
+                .collect()
+        } else {
+            frames.iter().rev().map(frame).collect()
+        };
+
+        Self { error, frames }
+    }
+}
+
+impl Display for StackTrace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl Error for StackTrace {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.error.source()
+    }
+}
+
+impl Diagnostic for StackTrace {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.error.code()
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        self.error.severity()
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.error.help()
+    }
+
+    fn url<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.error.url()
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        self.error.source_code()
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        self.error.labels()
+    }
+
+    fn related<'a>(&'a self) -> Option<Box<dyn Iterator<Item = &'a dyn Diagnostic> + 'a>> {
+        Some(Box::new(
+            self.frames.iter().map(|frame| frame as &dyn Diagnostic),
+        ))
+    }
+
+    fn diagnostic_source(&self) -> Option<&dyn Diagnostic> {
+        self.error.diagnostic_source()
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    collections::HashMap,
+    thread,
+    time::{Duration, Instant},
+};
+
+use miette::Report;
+use rand::{SeedableRng, rngs::StdRng};
+use regex::Regex;
+
+use crate::{
+    element::Element,
+    error::RuntimeError,
+    limits::{LimitExceeded, Limits},
+    stack::CallFrame,
+    value::Overflow,
+};
+
+pub const DEFAULT_FUNCTION_PREFIX: &str = "fn";
+
+// interpreter-wide state that isn't scoped like variables or specials
+#[derive(Debug)]
+pub struct State {
+    pub functions: HashMap<String, Vec<Element>>,
+    // elements with this namespace prefix always call the function of the same name, set by <program function-prefix>
+    pub function_prefix: String,
+    // the <struct> elements that define each struct, by name
+    pub structs: HashMap<String, Element>,
+    // the <enum> elements that define each enum, by name
+    pub enums: HashMap<String, Element>,
+    // how many loops of the current function the interpreted element is in, so <break> outside of them still leaves
+    // the nearest block like <return>
+    pub loops: u32,
+    // the <macro> elements that define each macro, by lowercase name
+    pub macros: HashMap<String, Element>,
+    // how many uses of macros have been expanded, which gives the variables of each expansion a unique name
+    pub expanded: u64,
+    pub rng: StdRng,
+    pub limits: Limits,
+    // the default for arithmetic elements without a `mode` attribute
+    pub overflow: Overflow,
+    // from the outermost call to the innermost one
+    pub call_stack: Vec<CallFrame>,
+    // the call stack at the point where the error that's currently propagating was raised
+    pub trace: Option<Vec<CallFrame>>,
+    // the errors handled by the <catch> elements that are running, with the stacks they were raised at, for <rethrow>
+    //
+    // an error is taken out when it's rethrown, since reports can't be cloned
+    pub caught: Vec<Option<(Report, Vec<CallFrame>)>>,
+    // compiled `pattern` attributes, by their source
+    patterns: HashMap<String, Regex>,
+    steps: u64,
+    started: Instant,
+}
+
+impl State {
+    // pass a seed to make `<rand>`, `<choice>` and `<shuffle>` reproducible
+    pub fn new(seed: Option<u64>) -> Self {
+        Self {
+            functions: HashMap::new(),
+            function_prefix: DEFAULT_FUNCTION_PREFIX.to_string(),
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            loops: 0,
+            macros: HashMap::new(),
+            expanded: 0,
+            rng: match seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_os_rng(),
+            },
+            limits: Limits::default(),
+            overflow: Overflow::default(),
+            call_stack: Vec::new(),
+            trace: None,
+            caught: Vec::new(),
+            patterns: HashMap::new(),
+            steps: 0,
+            started: Instant::now(),
+        }
+    }
+
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    // called once for every evaluated element
+    pub fn tick(&mut self) -> Result<(), LimitExceeded> {
+        self.steps += 1;
+
+        if let Some(max_steps) = self.limits.max_steps
+            && self.steps > max_steps
+        {
+            return Err(LimitExceeded::Steps(max_steps));
+        }
+
+        if let Some(timeout) = self.limits.timeout
+            && self.started.elapsed() > timeout
+        {
+            return Err(LimitExceeded::Timeout(timeout));
+        }
+
+        Ok(())
+    }
+
+    pub fn check_string_length(&self, length: usize) -> Result<(), LimitExceeded> {
+        match self.limits.max_string_length {
+            Some(max_length) if length > max_length => Err(LimitExceeded::StringLength(max_length)),
+            _ => Ok(()),
+        }
+    }
+
+    // a big integer's length in decimal digits, estimated from its number of bits, checked before computing it
+    pub fn check_bigint_bits(&self, bits: u64) -> Result<(), LimitExceeded> {
+        let digits = bits.saturating_mul(30_103) / 100_000 + 1;
+        self.check_string_length(usize::try_from(digits).unwrap_or(usize::MAX))
+    }
+
+    // sleeps for the duration, but only until the timeout runs out, in which case it stops the program
+    pub fn sleep(&self, duration: Duration) -> Result<(), LimitExceeded> {
+        if let Some(timeout) = self.limits.timeout {
+            let remaining = timeout.saturating_sub(self.started.elapsed());
+
+            if duration >= remaining {
+                thread::sleep(remaining);
+                return Err(LimitExceeded::Timeout(timeout));
+            }
+        }
+
+        thread::sleep(duration);
+        Ok(())
+    }
+
+    pub fn enter_call(&mut self, frame: CallFrame) -> Result<(), LimitExceeded> {
+        if let Some(max_call_depth) = self.limits.max_call_depth
+            && self.call_stack.len() >= max_call_depth
+        {
+            return Err(LimitExceeded::CallDepth(max_call_depth));
+        }
+
+        self.call_stack.push(frame);
+        Ok(())
+    }
+
+    pub fn exit_call(&mut self) {
+        self.call_stack.pop();
+    }
+
+    // keeps the innermost stack an error was seen at, so the outer calls don't overwrite it
+    pub fn record_trace(&mut self) {
+        if self.trace.is_none() {
+            self.trace = Some(self.call_stack.clone());
+        }
+    }
+
+    // the compiled `pattern` attribute of an element, compiling it the first time it's needed
+    pub fn pattern(&mut self, element: &Element) -> Result<Regex, RuntimeError> {
+        let pattern = element
+            .attributes
+            .get("pattern")
+            .ok_or_else(|| RuntimeError::missing_attribute(&element.name, "pattern"))?;
+
+        if let Some(regex) = self.patterns.get(pattern) {
+            return Ok(regex.clone());
+        }
+
+        let regex = Regex::new(pattern).map_err(|err| RuntimeError::InvalidPattern {
+            pattern: pattern.clone(),
+            message: err.to_string(),
+        })?;
+
+        self.patterns.insert(pattern.clone(), regex.clone());
+
+        Ok(regex)
+    }
+
+    // the stack at the point the currently handled error was raised
+    pub fn take_trace(&mut self) -> Vec<CallFrame> {
+        self.trace.take().unwrap_or_else(|| self.call_stack.clone())
+    }
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
@@ -2,21 +2,44 @@ use std::{
     cmp::Ordering,
     convert::Infallible,
     fmt::{Display, Formatter},
-    iter::Sum,
-    ops::{Add, AddAssign, Div, Mul, Neg, Not, Rem, Sub},
+    ops::Not,
     str::FromStr,
 };
 
-use miette::Diagnostic;
-use thiserror::Error;
+use num_bigint::BigInt;
+use num_traits::{FromPrimitive, Signed, ToPrimitive, Zero};
+use rust_decimal::Decimal;
 
-#[derive(Debug, Clone, PartialEq)]
+use crate::{element::Element, error::RuntimeError};
+
+#[derive(Debug, Clone, Default)]
 pub enum Value {
+    #[default]
     Null,
     Int(i64),
     Float(f64),
     Bool(bool),
     Str(String),
+    List(Vec<Value>),
+    BigInt(BigInt),
+    Decimal(Decimal),
+    // an instance of a struct defined with <struct>, with its fields in the order they were defined in
+    Struct {
+        name: String,
+        fields: Vec<(String, Value)>,
+    },
+    // a variant of an enum defined with <enum>, boxed to keep values small
+    Enum(Box<Variant>),
+    // a piece of code captured with <quote> or built with <node>
+    Node(Box<Element>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    // the name of the enum
+    pub name: String,
+    pub variant: String,
+    pub fields: Vec<(String, Value)>,
 }
 
 impl Value {
@@ -31,6 +54,9 @@ impl Value {
             Self::Float(value) => Some(*value as i64),
             Self::Bool(value) => Some(if *value { 1 } else { 0 }),
             Self::Str(value) => value.parse::<i64>().ok(),
+            Self::List(_) | Self::Struct { .. } | Self::Enum(_) | Self::Node(_) => None,
+            Self::BigInt(value) => value.to_i64(),
+            Self::Decimal(value) => value.to_i64(),
         }
     }
 
@@ -41,6 +67,39 @@ impl Value {
             Self::Float(value) => Some(*value),
             Self::Bool(value) => Some(if *value { 1.0 } else { 0.0 }),
             Self::Str(value) => value.parse::<f64>().ok(),
+            Self::List(_) | Self::Struct { .. } | Self::Enum(_) | Self::Node(_) => None,
+            Self::BigInt(value) => value.to_f64(),
+            Self::Decimal(value) => value.to_f64(),
+        }
+    }
+
+    pub fn as_bigint(&self) -> Option<BigInt> {
+        match self {
+            Self::Null => Some(BigInt::zero()),
+            Self::Int(value) => Some(BigInt::from(*value)),
+            Self::Float(value) => BigInt::from_f64(value.trunc()),
+            Self::Bool(value) => Some(BigInt::from(*value as i64)),
+            Self::Str(value) => value.parse().ok(),
+            Self::List(_) | Self::Struct { .. } | Self::Enum(_) | Self::Node(_) => None,
+            Self::BigInt(value) => Some(value.clone()),
+            Self::Decimal(value) => value.trunc().to_string().parse().ok(),
+        }
+    }
+
+    // `None` if the value doesn't fit in a decimal, which holds up to 28 significant digits
+    pub fn as_decimal(&self) -> Option<Decimal> {
+        match self {
+            Self::Null => Some(Decimal::ZERO),
+            Self::Int(value) => Some(Decimal::from(*value)),
+            Self::Float(value) => Decimal::from_f64(*value),
+            Self::Bool(value) => Some(Decimal::from(*value as i64)),
+            Self::Str(value) => value
+                .parse()
+                .ok()
+                .or_else(|| Decimal::from_scientific(value).ok()),
+            Self::List(_) | Self::Struct { .. } | Self::Enum(_) | Self::Node(_) => None,
+            Self::BigInt(value) => value.to_string().parse().ok(),
+            Self::Decimal(value) => Some(*value),
         }
     }
 
@@ -54,9 +113,28 @@ impl Value {
                 value.to_lowercase().as_str(),
                 "false" | "0" | "off" | "no" | ""
             ),
+            Self::List(items) => !items.is_empty(),
+            Self::BigInt(value) => !value.is_zero(),
+            Self::Decimal(value) => !value.is_zero(),
+            Self::Struct { .. } | Self::Enum(_) | Self::Node(_) => true,
         }
     }
 
+    // a value of the built-in type with the given name, to convert other values to with `convert_to`
+    pub fn of_type(name: &str) -> Option<Self> {
+        Some(match name.to_lowercase().as_str() {
+            "null" => Self::Null,
+            "int" | "integer" => Self::Int(0),
+            "float" => Self::Float(0.0),
+            "bool" | "boolean" => Self::Bool(false),
+            "string" | "str" => Self::Str(String::new()),
+            "list" => Self::List(Vec::new()),
+            "bigint" => Self::BigInt(BigInt::zero()),
+            "decimal" => Self::Decimal(Decimal::ZERO),
+            _ => return None,
+        })
+    }
+
     pub fn convert_to(&self, target_type: &Self) -> Option<Self> {
         match target_type {
             Self::Null => Some(Self::Null),
@@ -64,6 +142,16 @@ impl Value {
             Self::Float(_) => self.as_float().map(Self::Float),
             Self::Bool(_) => Some(Self::Bool(self.as_bool())),
             Self::Str(_) => Some(Self::Str(self.to_string())),
+            Self::List(_) => Some(match self {
+                Self::Null => Self::List(Vec::new()),
+                Self::List(items) => Self::List(items.clone()),
+                other => Self::List(vec![other.clone()]),
+            }),
+            Self::BigInt(_) => self.as_bigint().map(Self::BigInt),
+            Self::Decimal(_) => self.as_decimal().map(Self::Decimal),
+            Self::Struct { .. } | Self::Enum(_) | Self::Node(_) => {
+                (self.type_name() == target_type.type_name()).then(|| self.clone())
+            }
         }
     }
 
@@ -74,8 +162,25 @@ impl Value {
             Self::Float(_) => "float".to_string(),
             Self::Bool(_) => "bool".to_string(),
             Self::Str(_) => "string".to_string(),
+            Self::List(_) => "list".to_string(),
+            Self::BigInt(_) => "bigint".to_string(),
+            Self::Decimal(_) => "decimal".to_string(),
+            Self::Struct { name, .. } => name.clone(),
+            Self::Enum(variant) => variant.name.clone(),
+            Self::Node(_) => "node".to_string(),
         }
     }
+
+    fn fields_to_string(fields: &[(String, Value)]) -> String {
+        format!(
+            "{{ {} }}",
+            fields
+                .iter()
+                .map(|(field, value)| format!("{field}: {value}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
 }
 
 impl Display for Value {
@@ -92,17 +197,30 @@ impl Display for Value {
                 }
             }
             Self::Str(value) => value.clone(),
+            Self::List(items) => format!(
+                "[{}]",
+                items
+                    .iter()
+                    .map(|item| item.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Self::BigInt(value) => value.to_string(),
+            Self::Decimal(value) => value.to_string(),
+            Self::Struct { name, fields } if fields.is_empty() => format!("{name} {{}}"),
+            Self::Struct { name, fields } => format!("{name} {}", Self::fields_to_string(fields)),
+            Self::Enum(variant) if variant.fields.is_empty() => variant.variant.clone(),
+            Self::Enum(variant) => format!(
+                "{} {}",
+                variant.variant,
+                Self::fields_to_string(&variant.fields)
+            ),
+            Self::Node(element) => element.to_string(),
         };
         write!(f, "{string}")
     }
 }
 
-impl Default for Value {
-    fn default() -> Self {
-        Self::Null
-    }
-}
-
 impl From<()> for Value {
     fn from(_: ()) -> Self {
         Self::Null
@@ -133,6 +251,24 @@ impl From<String> for Value {
     }
 }
 
+impl From<Vec<Value>> for Value {
+    fn from(value: Vec<Value>) -> Self {
+        Self::List(value)
+    }
+}
+
+impl From<BigInt> for Value {
+    fn from(value: BigInt) -> Self {
+        Self::BigInt(value)
+    }
+}
+
+impl From<Decimal> for Value {
+    fn from(value: Decimal) -> Self {
+        Self::Decimal(value)
+    }
+}
+
 impl From<&str> for Value {
     fn from(value: &str) -> Self {
         Self::Str(value.to_string())
@@ -147,136 +283,361 @@ impl FromStr for Value {
     }
 }
 
-impl Add for Value {
-    type Output = Self;
+// what integer arithmetic does when the result doesn't fit in an `i64`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Overflow {
+    // throw an `IntegerOverflow` error
+    #[default]
+    Checked,
+    // wrap around, like two's complement hardware does
+    Wrapping,
+    // stop at the minimum or maximum value
+    Saturating,
+    // turn the result into a big integer
+    Promote,
+}
 
-    fn add(self, other: Self) -> Self::Output {
-        match (self, other) {
-            (Self::Null, other) | (other, Self::Null) => other,
+impl Overflow {
+    pub fn apply(
+        self,
+        operation: &str,
+        checked: Option<i64>,
+        wrapping: impl FnOnce() -> i64,
+        saturating: impl FnOnce() -> i64,
+        promoted: impl FnOnce() -> BigInt,
+    ) -> Result<Value, RuntimeError> {
+        match (self, checked) {
+            (_, Some(result)) => Ok(Value::Int(result)),
+            (Self::Checked, None) => Err(RuntimeError::IntegerOverflow(operation.to_string())),
+            (Self::Wrapping, None) => Ok(Value::Int(wrapping())),
+            (Self::Saturating, None) => Ok(Value::Int(saturating())),
+            (Self::Promote, None) => Ok(Value::BigInt(promoted())),
+        }
+    }
+}
 
-            (Self::Int(a), Self::Int(b)) => Self::Int(a + b),
-            (Self::Float(a), Self::Float(b)) => Self::Float(a + b),
+impl FromStr for Overflow {
+    type Err = String;
 
-            (Self::Int(i), Self::Float(f)) | (Self::Float(f), Self::Int(i)) => {
-                Self::Float(i as f64 + f)
-            }
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "checked" => Ok(Self::Checked),
+            "wrapping" => Ok(Self::Wrapping),
+            "saturating" => Ok(Self::Saturating),
+            "promote" => Ok(Self::Promote),
+            _ => Err(format!(
+                "`{s}` isn't one of `checked`, `wrapping`, `saturating` or `promote`"
+            )),
+        }
+    }
+}
 
-            (Self::Bool(a), Self::Bool(b)) => Self::Bool(a || b),
+// two numbers converted to the wider of their types, for operations involving big integers or decimals
+enum Numbers {
+    Big(BigInt, BigInt),
+    Decimal(Decimal, Decimal),
+    Float(f64, f64),
+}
 
-            (b @ Self::Bool(_), other) | (other, b @ Self::Bool(_)) => {
-                b.convert_to(&other).unwrap() + other
-            }
+impl Numbers {
+    // `None` unless both values are numbers and at least one of them is a big integer or a decimal
+    fn of(a: &Value, b: &Value) -> Option<Result<Self, RuntimeError>> {
+        let numeric = |value: &Value| {
+            matches!(
+                value,
+                Value::Int(_)
+                    | Value::Float(_)
+                    | Value::Bool(_)
+                    | Value::BigInt(_)
+                    | Value::Decimal(_)
+            )
+        };
+        let wide = |value: &Value| matches!(value, Value::BigInt(_) | Value::Decimal(_));
 
-            (Self::Str(a), Self::Str(b)) => Self::Str(a + &b),
+        if !numeric(a) || !numeric(b) || !(wide(a) || wide(b)) {
+            return None;
+        }
+
+        let either = |f: fn(&Value) -> bool| f(a) || f(b);
+
+        Some(if either(|value| matches!(value, Value::Float(_))) {
+            Ok(Self::Float(
+                a.as_float().unwrap_or(f64::NAN),
+                b.as_float().unwrap_or(f64::NAN),
+            ))
+        } else if either(|value| matches!(value, Value::Decimal(_))) {
+            let decimal = |value: &Value| {
+                value.as_decimal().ok_or_else(|| {
+                    RuntimeError::DecimalOverflow(format!("convert {value} to a decimal"))
+                })
+            };
+
+            decimal(a).and_then(|a| Ok(Self::Decimal(a, decimal(b)?)))
+        } else {
+            Ok(Self::Big(
+                a.as_bigint().unwrap_or_default(),
+                b.as_bigint().unwrap_or_default(),
+            ))
+        })
+    }
+
+    fn decimal(operation: &str, result: Option<Decimal>) -> Result<Value, RuntimeError> {
+        result
+            .map(Value::Decimal)
+            .ok_or_else(|| RuntimeError::DecimalOverflow(operation.to_string()))
+    }
+
+    fn is_zero(&self) -> bool {
+        match self {
+            Self::Big(_, b) => b.is_zero(),
+            Self::Decimal(_, b) => b.is_zero(),
+            Self::Float(_, b) => *b == 0.0,
+        }
+    }
 
-            (Self::Str(s), Self::Int(i)) => Self::Str(s + &i.to_string()),
-            (Self::Int(i), Self::Str(s)) => Self::Str(i.to_string() + &s),
-            (Self::Str(s), Self::Float(f)) => Self::Str(s + &f.to_string()),
-            (Self::Float(f), Self::Str(s)) => Self::Str(f.to_string() + &s),
+    fn add(self) -> Result<Value, RuntimeError> {
+        match self {
+            Self::Big(a, b) => Ok(Value::BigInt(a + b)),
+            Self::Decimal(a, b) => Self::decimal("add", a.checked_add(b)),
+            Self::Float(a, b) => Ok(Value::Float(a + b)),
+        }
+    }
+
+    fn sub(self) -> Result<Value, RuntimeError> {
+        match self {
+            Self::Big(a, b) => Ok(Value::BigInt(a - b)),
+            Self::Decimal(a, b) => Self::decimal("subtract", a.checked_sub(b)),
+            Self::Float(a, b) => Ok(Value::Float(a - b)),
+        }
+    }
+
+    fn mul(self) -> Result<Value, RuntimeError> {
+        match self {
+            Self::Big(a, b) => Ok(Value::BigInt(a * b)),
+            Self::Decimal(a, b) => Self::decimal("multiply", a.checked_mul(b)),
+            Self::Float(a, b) => Ok(Value::Float(a * b)),
+        }
+    }
+
+    fn div(self) -> Result<Value, RuntimeError> {
+        if self.is_zero() {
+            return Err(RuntimeError::DivisionByZero);
+        }
+
+        match self {
+            Self::Big(a, b) => Ok(Value::BigInt(a / b)),
+            Self::Decimal(a, b) => Self::decimal("divide", a.checked_div(b)),
+            Self::Float(a, b) => Ok(Value::Float(a / b)),
+        }
+    }
+
+    fn rem(self) -> Result<Value, RuntimeError> {
+        if self.is_zero() {
+            return Err(RuntimeError::DivisionByZero);
+        }
+
+        match self {
+            Self::Big(a, b) => Ok(Value::BigInt(a % b)),
+            Self::Decimal(a, b) => Self::decimal("modulo", a.checked_rem(b)),
+            Self::Float(a, b) => Ok(Value::Float(a % b)),
         }
     }
 }
 
-impl Sum for Value {
-    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
-        iter.fold(Self::Null, |acc, value| acc + value)
+// a number as the exact fraction `numerator / denominator`, so numbers of any types can be compared without rounding
+// either of them, `None` for other values and for infinite or NaN floats
+fn fraction(value: &Value) -> Option<(BigInt, BigInt)> {
+    match value {
+        Value::Int(i) => Some((BigInt::from(*i), BigInt::from(1))),
+        Value::Bool(b) => Some((BigInt::from(u8::from(*b)), BigInt::from(1))),
+        Value::BigInt(b) => Some((b.clone(), BigInt::from(1))),
+        Value::Decimal(d) => Some((BigInt::from(d.mantissa()), BigInt::from(10).pow(d.scale()))),
+        Value::Float(f) if f.is_finite() => {
+            let (mantissa, exponent, sign) = num_traits::float::FloatCore::integer_decode(*f);
+            let numerator = BigInt::from(mantissa) * i64::from(sign);
+
+            Some(if exponent < 0 {
+                (numerator, BigInt::from(1) << exponent.unsigned_abs())
+            } else {
+                (numerator << exponent.unsigned_abs(), BigInt::from(1))
+            })
+        }
+        _ => None,
     }
 }
 
-impl AddAssign for Value {
-    fn add_assign(&mut self, other: Self) {
-        // TODO: modify in-place instead of cloning
-        *self = self.clone() + other;
+// compares two numbers, or booleans as 0 and 1, by their exact values whatever their types, so that comparing is
+// transitive across types; `None` if either is NaN
+fn compare_numbers(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Int(a), Value::Int(b)) => a.partial_cmp(b),
+        (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
+        // infinities are beyond every finite number
+        (Value::Float(f), _) if !f.is_finite() => f.partial_cmp(&0.0),
+        (_, Value::Float(f)) if !f.is_finite() => 0.0.partial_cmp(f),
+        _ => {
+            let (a_numerator, a_denominator) = fraction(a)?;
+            let (b_numerator, b_denominator) = fraction(b)?;
+
+            (a_numerator * b_denominator).partial_cmp(&(b_numerator * a_denominator))
+        }
     }
 }
 
-// an error raised when trying to perform operations on incompatible types
-#[derive(Debug, Clone, Error, Diagnostic)]
-#[error("Can't {operation} incompatible type{}: {}{}", if .b.is_some() { "s" } else { "" }, .a.type_name(), if let Some(right) = .b { format!(" and {}", right.type_name()) } else { String::new() })]
-pub struct OperationIncompatibleTypesError {
-    operation: String,
-    pub a: Value,
-    pub b: Option<Value>,
+fn is_number(value: &Value) -> bool {
+    matches!(
+        value,
+        Value::Int(_) | Value::Float(_) | Value::BigInt(_) | Value::Decimal(_)
+    )
 }
 
-impl Neg for Value {
-    type Output = Result<Self, OperationIncompatibleTypesError>;
+// the arithmetic operations take the overflow mode for integers, so they can't implement the `std::ops` traits
+impl Value {
+    pub fn add(self, other: Self, overflow: Overflow) -> Result<Self, RuntimeError> {
+        if let Some(numbers) = Numbers::of(&self, &other) {
+            return numbers?.add();
+        }
+
+        Ok(match (self, other) {
+            (Self::Null, other) | (other, Self::Null) => other,
+
+            (Self::List(mut a), Self::List(b)) => {
+                a.extend(b);
+                Self::List(a)
+            }
+            (Self::List(mut items), other) => {
+                items.push(other);
+                Self::List(items)
+            }
+            (other, Self::List(mut items)) => {
+                items.insert(0, other);
+                Self::List(items)
+            }
+
+            (Self::Int(a), Self::Int(b)) => overflow.apply(
+                "add",
+                a.checked_add(b),
+                || a.wrapping_add(b),
+                || a.saturating_add(b),
+                || BigInt::from(a) + b,
+            )?,
+            (Self::Float(a), Self::Float(b)) => Self::Float(a + b),
 
-    fn neg(self) -> Self::Output {
+            (Self::Int(i), Self::Float(f)) | (Self::Float(f), Self::Int(i)) => {
+                Self::Float(i as f64 + f)
+            }
+
+            (Self::Bool(a), Self::Bool(b)) => Self::Bool(a || b),
+
+            (b @ Self::Bool(_), other) | (other, b @ Self::Bool(_)) => {
+                return match b.convert_to(&other) {
+                    Some(converted) => converted.add(other, overflow),
+                    // structs, enums and nodes can't be made from a boolean
+                    None => Err(RuntimeError::IncompatibleTypes {
+                        operation: "add".to_string(),
+                        a: b,
+                        b: Some(other),
+                    }),
+                };
+            }
+
+            (Self::Str(a), Self::Str(b)) => Self::Str(a + &b),
+
+            (Self::Str(s), other) => Self::Str(s + &other.to_string()),
+            (other, Self::Str(s)) => Self::Str(other.to_string() + &s),
+
+            // all the other combinations of numbers are handled by `Numbers`
+            (a, b) => {
+                return Err(RuntimeError::IncompatibleTypes {
+                    operation: "add".to_string(),
+                    a,
+                    b: Some(b),
+                });
+            }
+        })
+    }
+
+    pub fn neg(self, overflow: Overflow) -> Result<Self, RuntimeError> {
         match self {
             Self::Null => Ok(Self::Null),
-            Self::Int(value) => Ok(Self::Int(-value)),
+            Self::Int(value) => overflow.apply(
+                "arithmetically negate",
+                value.checked_neg(),
+                || value.wrapping_neg(),
+                || value.saturating_neg(),
+                || -BigInt::from(value),
+            ),
             Self::Float(value) => Ok(Self::Float(-value)),
-            Self::Bool(_) => Err(OperationIncompatibleTypesError {
-                operation: "arithmetically negate".to_string(),
-                a: self,
-                b: None,
-            }),
-            Self::Str(_) => Err(OperationIncompatibleTypesError {
+            Self::BigInt(value) => Ok(Self::BigInt(-value)),
+            Self::Decimal(value) => Ok(Self::Decimal(-value)),
+            Self::Bool(_)
+            | Self::Str(_)
+            | Self::List(_)
+            | Self::Struct { .. }
+            | Self::Enum(_)
+            | Self::Node(_) => Err(RuntimeError::IncompatibleTypes {
                 operation: "arithmetically negate".to_string(),
                 a: self,
                 b: None,
             }),
         }
     }
-}
-
-impl Not for Value {
-    type Output = Self;
 
-    fn not(self) -> Self::Output {
-        Self::Bool(!self.as_bool())
-    }
-}
-
-pub trait Abs {
-    type Output;
-    fn abs(self) -> Self::Output;
-}
-
-impl Abs for Value {
-    type Output = Result<Self, OperationIncompatibleTypesError>;
-    fn abs(self) -> Self::Output {
+    pub fn abs(self, overflow: Overflow) -> Result<Self, RuntimeError> {
         match self {
             Self::Null => Ok(Self::Null),
-            Self::Int(value) => Ok(Self::Int(value.abs())),
+            Self::Int(value) => overflow.apply(
+                "compute absolute value of",
+                value.checked_abs(),
+                || value.wrapping_abs(),
+                || value.saturating_abs(),
+                || BigInt::from(value).abs(),
+            ),
             Self::Float(value) => Ok(Self::Float(value.abs())),
-            Self::Bool(_) => Err(OperationIncompatibleTypesError {
-                operation: "compute absolute value of".to_string(),
-                a: self,
-                b: None,
-            }),
-            Self::Str(_) => Err(OperationIncompatibleTypesError {
+            Self::BigInt(value) => Ok(Self::BigInt(value.abs())),
+            Self::Decimal(value) => Ok(Self::Decimal(value.abs())),
+            Self::Bool(_)
+            | Self::Str(_)
+            | Self::List(_)
+            | Self::Struct { .. }
+            | Self::Enum(_)
+            | Self::Node(_) => Err(RuntimeError::IncompatibleTypes {
                 operation: "compute absolute value of".to_string(),
                 a: self,
                 b: None,
             }),
         }
     }
-}
 
-impl Sub for Value {
-    type Output = Result<Self, OperationIncompatibleTypesError>;
+    pub fn sub(self, other: Self, overflow: Overflow) -> Result<Self, RuntimeError> {
+        if let Some(numbers) = Numbers::of(&self, &other) {
+            return numbers?.sub();
+        }
 
-    fn sub(self, other: Self) -> Self::Output {
         match (self, other) {
             (other, Self::Null) => Ok(other),
 
-            (s @ Self::Str(_), other) => Err(OperationIncompatibleTypesError {
+            (s @ (Self::Str(_) | Self::List(_)), other) => Err(RuntimeError::IncompatibleTypes {
                 operation: "subtract".to_string(),
                 a: s,
                 b: Some(other),
             }),
 
-            (other, s @ Self::Str(_)) => Err(OperationIncompatibleTypesError {
+            (other, s @ (Self::Str(_) | Self::List(_))) => Err(RuntimeError::IncompatibleTypes {
                 operation: "subtract".to_string(),
                 a: other,
                 b: Some(s),
             }),
 
-            (Self::Null, other) => -other,
+            (Self::Null, other) => other.neg(overflow),
 
-            (Self::Int(a), Self::Int(b)) => Ok(Self::Int(a - b)),
+            (Self::Int(a), Self::Int(b)) => overflow.apply(
+                "subtract",
+                a.checked_sub(b),
+                || a.wrapping_sub(b),
+                || a.saturating_sub(b),
+                || BigInt::from(a) - b,
+            ),
             (Self::Float(a), Self::Float(b)) => Ok(Self::Float(a - b)),
 
             (Self::Int(i), Self::Float(f)) => Ok(Self::Float(i as f64 - f)),
@@ -286,22 +647,53 @@ impl Sub for Value {
                 Ok(Self::Int(a.as_int().unwrap() - b.as_int().unwrap()))
             }
 
-            (b @ Self::Bool(_), Self::Int(i)) => Ok(Self::Int(b.as_int().unwrap() - i)),
+            (b @ Self::Bool(_), Self::Int(i)) => {
+                Self::Int(b.as_int().unwrap()).sub(Self::Int(i), overflow)
+            }
             (b @ Self::Bool(_), Self::Float(f)) => Ok(Self::Float(b.as_float().unwrap() - f)),
-            (Self::Int(i), b @ Self::Bool(_)) => Ok(Self::Int(i - b.as_int().unwrap())),
+            (Self::Int(i), b @ Self::Bool(_)) => {
+                Self::Int(i).sub(Self::Int(b.as_int().unwrap()), overflow)
+            }
             (Self::Float(f), b @ Self::Bool(_)) => Ok(Self::Float(f - b.as_float().unwrap())),
+
+            // all the other combinations of numbers are handled by `Numbers`
+            (a, b) => Err(RuntimeError::IncompatibleTypes {
+                operation: "subtract".to_string(),
+                a,
+                b: Some(b),
+            }),
         }
     }
-}
 
-impl Mul for Value {
-    type Output = Result<Self, OperationIncompatibleTypesError>;
-
-    fn mul(self, other: Self) -> Self::Output {
+    pub fn mul(self, other: Self, overflow: Overflow) -> Result<Self, RuntimeError> {
         Ok(match (self, other) {
             (Self::Null, _) | (_, Self::Null) => Self::Null,
 
-            (Self::Int(a), Self::Int(b)) => Self::Int(a * b),
+            (l @ Self::List(_), other) | (other, l @ Self::List(_)) => {
+                return Err(RuntimeError::IncompatibleTypes {
+                    operation: "multiply".to_string(),
+                    a: l,
+                    b: Some(other),
+                });
+            }
+
+            // otherwise multiplying by `true` would return them unchanged
+            (s @ (Self::Struct { .. } | Self::Enum(_) | Self::Node(_)), other)
+            | (other, s @ (Self::Struct { .. } | Self::Enum(_) | Self::Node(_))) => {
+                return Err(RuntimeError::IncompatibleTypes {
+                    operation: "multiply".to_string(),
+                    a: s,
+                    b: Some(other),
+                });
+            }
+
+            (Self::Int(a), Self::Int(b)) => overflow.apply(
+                "multiply",
+                a.checked_mul(b),
+                || a.wrapping_mul(b),
+                || a.saturating_mul(b),
+                || BigInt::from(a) * b,
+            )?,
             (Self::Float(a), Self::Float(b)) => Self::Float(a * b),
 
             (Self::Int(i), Self::Float(f)) | (Self::Float(f), Self::Int(i)) => {
@@ -319,7 +711,7 @@ impl Mul for Value {
             }
 
             (a @ Self::Str(_), b @ Self::Str(_)) => {
-                return Err(OperationIncompatibleTypesError {
+                return Err(RuntimeError::IncompatibleTypes {
                     operation: "multiply".to_string(),
                     a,
                     b: Some(b),
@@ -345,50 +737,55 @@ impl Mul for Value {
                     Self::Str(s.repeat(f as usize))
                 }
             }
+
+            (a, b) => match Numbers::of(&a, &b) {
+                Some(numbers) => numbers?.mul()?,
+                None => {
+                    return Err(RuntimeError::IncompatibleTypes {
+                        operation: "multiply".to_string(),
+                        a,
+                        b: Some(b),
+                    });
+                }
+            },
         })
     }
-}
-
-#[derive(Debug, Clone, Error, Diagnostic)]
-pub enum DivisionError {
-    #[error("Division by zero is not allowed")]
-    DivisionByZero,
 
-    #[error(transparent)]
-    IncompatibleTypes(#[from] OperationIncompatibleTypesError),
-}
-
-impl Div for Value {
-    type Output = Result<Self, DivisionError>;
+    pub fn div(self, other: Self, overflow: Overflow) -> Result<Self, RuntimeError> {
+        if let Some(numbers) = Numbers::of(&self, &other) {
+            return numbers?.div();
+        }
 
-    fn div(self, other: Self) -> Self::Output {
         match (self, other) {
             (Self::Null, Self::Null) => Ok(Self::Null),
-            (Self::Null, other) => Err(DivisionError::IncompatibleTypes(
-                OperationIncompatibleTypesError {
-                    operation: "divide".to_string(),
-                    a: Self::Null,
-                    b: Some(other),
-                },
-            )),
-            (other, Self::Null) => Err(DivisionError::IncompatibleTypes(
-                OperationIncompatibleTypesError {
-                    operation: "divide".to_string(),
-                    a: other,
-                    b: Some(Self::Null),
-                },
-            )),
+            (Self::Null, other) => Err(RuntimeError::IncompatibleTypes {
+                operation: "divide".to_string(),
+                a: Self::Null,
+                b: Some(other),
+            }),
+            (other, Self::Null) => Err(RuntimeError::IncompatibleTypes {
+                operation: "divide".to_string(),
+                a: other,
+                b: Some(Self::Null),
+            }),
 
             (Self::Int(a), Self::Int(b)) => {
                 if b == 0 {
-                    Err(DivisionError::DivisionByZero)
+                    Err(RuntimeError::DivisionByZero)
                 } else {
-                    Ok(Self::Int(a / b))
+                    // `i64::MIN / -1` is the only division that overflows
+                    overflow.apply(
+                        "divide",
+                        a.checked_div(b),
+                        || a.wrapping_div(b),
+                        || a.saturating_div(b),
+                        || BigInt::from(a) / b,
+                    )
                 }
             }
             (Self::Float(a), Self::Float(b)) => {
                 if b == 0.0 {
-                    Err(DivisionError::DivisionByZero)
+                    Err(RuntimeError::DivisionByZero)
                 } else {
                     Ok(Self::Float(a / b))
                 }
@@ -396,14 +793,14 @@ impl Div for Value {
 
             (Self::Int(i), Self::Float(f)) => {
                 if f == 0.0 {
-                    Err(DivisionError::DivisionByZero)
+                    Err(RuntimeError::DivisionByZero)
                 } else {
                     Ok(Self::Float(i as f64 / f))
                 }
             }
             (Self::Float(f), Self::Int(i)) => {
                 if i == 0 {
-                    Err(DivisionError::DivisionByZero)
+                    Err(RuntimeError::DivisionByZero)
                 } else {
                     Ok(Self::Float(f / i as f64))
                 }
@@ -411,7 +808,7 @@ impl Div for Value {
 
             (Self::Bool(a), Self::Bool(b)) => {
                 if !b {
-                    return Err(DivisionError::DivisionByZero);
+                    return Err(RuntimeError::DivisionByZero);
                 }
 
                 Ok(Self::Int(
@@ -420,82 +817,68 @@ impl Div for Value {
             }
 
             (b @ Self::Bool(_), Self::Int(i)) => {
-                if i == 0 {
-                    return Err(DivisionError::DivisionByZero);
-                }
-                Ok(Self::Int(b.as_int().unwrap() / i))
+                Self::Int(b.as_int().unwrap()).div(Self::Int(i), overflow)
             }
             (b @ Self::Bool(_), Self::Float(f)) => {
                 if f == 0.0 {
-                    Err(DivisionError::DivisionByZero)
+                    Err(RuntimeError::DivisionByZero)
                 } else {
                     Ok(Self::Float(b.as_float().unwrap() / f))
                 }
             }
             (Self::Int(i), Self::Bool(b)) => {
                 if !b {
-                    Err(DivisionError::DivisionByZero)
+                    Err(RuntimeError::DivisionByZero)
                 } else {
                     Ok(Self::Int(i / Self::Bool(b).as_int().unwrap()))
                 }
             }
             (Self::Float(f), Self::Bool(b)) => {
                 if !b {
-                    Err(DivisionError::DivisionByZero)
+                    Err(RuntimeError::DivisionByZero)
                 } else {
                     Ok(Self::Float(f / Self::Bool(b).as_float().unwrap()))
                 }
             }
 
-            (s @ Self::Str(_), other) => Err(DivisionError::IncompatibleTypes(
-                OperationIncompatibleTypesError {
-                    operation: "divide".to_string(),
-                    a: s,
-                    b: Some(other),
-                },
-            )),
-            (other, s @ Self::Str(_)) => Err(DivisionError::IncompatibleTypes(
-                OperationIncompatibleTypesError {
-                    operation: "divide".to_string(),
-                    a: other,
-                    b: Some(s),
-                },
-            )),
+            // strings, lists, and all the other combinations of numbers, which are handled by `Numbers`
+            (a, b) => Err(RuntimeError::IncompatibleTypes {
+                operation: "divide".to_string(),
+                a,
+                b: Some(b),
+            }),
         }
     }
-}
 
-impl Rem for Value {
-    type Output = Result<Self, DivisionError>;
+    pub fn rem(self, other: Self) -> Result<Self, RuntimeError> {
+        if let Some(numbers) = Numbers::of(&self, &other) {
+            return numbers?.rem();
+        }
 
-    fn rem(self, other: Self) -> Self::Output {
         match (self, other) {
             (Self::Null, Self::Null) => Ok(Self::Null),
-            (Self::Null, other) => Err(DivisionError::IncompatibleTypes(
-                OperationIncompatibleTypesError {
-                    operation: "modulo".to_string(),
-                    a: Self::Null,
-                    b: Some(other),
-                },
-            )),
-            (other, Self::Null) => Err(DivisionError::IncompatibleTypes(
-                OperationIncompatibleTypesError {
-                    operation: "modulo".to_string(),
-                    a: other,
-                    b: Some(Self::Null),
-                },
-            )),
+            (Self::Null, other) => Err(RuntimeError::IncompatibleTypes {
+                operation: "modulo".to_string(),
+                a: Self::Null,
+                b: Some(other),
+            }),
+            (other, Self::Null) => Err(RuntimeError::IncompatibleTypes {
+                operation: "modulo".to_string(),
+                a: other,
+                b: Some(Self::Null),
+            }),
 
             (Self::Int(a), Self::Int(b)) => {
                 if b == 0 {
-                    Err(DivisionError::DivisionByZero)
+                    Err(RuntimeError::DivisionByZero)
                 } else {
-                    Ok(Self::Int(a % b))
+                    // `wrapping_rem` doesn't overflow computing the remainder of `i64::MIN / -1`, which is 0
+                    Ok(Self::Int(a.wrapping_rem(b)))
                 }
             }
             (Self::Float(a), Self::Float(b)) => {
                 if b == 0.0 {
-                    Err(DivisionError::DivisionByZero)
+                    Err(RuntimeError::DivisionByZero)
                 } else {
                     Ok(Self::Float(a % b))
                 }
@@ -503,14 +886,14 @@ impl Rem for Value {
 
             (Self::Int(i), Self::Float(f)) => {
                 if f == 0.0 {
-                    Err(DivisionError::DivisionByZero)
+                    Err(RuntimeError::DivisionByZero)
                 } else {
                     Ok(Self::Float(i as f64 % f))
                 }
             }
             (Self::Float(f), Self::Int(i)) => {
                 if i == 0 {
-                    Err(DivisionError::DivisionByZero)
+                    Err(RuntimeError::DivisionByZero)
                 } else {
                     Ok(Self::Float(f % i as f64))
                 }
@@ -518,7 +901,7 @@ impl Rem for Value {
 
             (Self::Bool(a), Self::Bool(b)) => {
                 if !b {
-                    return Err(DivisionError::DivisionByZero);
+                    return Err(RuntimeError::DivisionByZero);
                 }
 
                 Ok(Self::Int(
@@ -526,73 +909,166 @@ impl Rem for Value {
                 ))
             }
 
-            (b @ Self::Bool(_), Self::Int(i)) => {
-                if i == 0 {
-                    return Err(DivisionError::DivisionByZero);
-                }
-                Ok(Self::Int(b.as_int().unwrap() % i))
-            }
+            (b @ Self::Bool(_), Self::Int(i)) => Self::Int(b.as_int().unwrap()).rem(Self::Int(i)),
             (b @ Self::Bool(_), Self::Float(f)) => {
                 if f == 0.0 {
-                    Err(DivisionError::DivisionByZero)
+                    Err(RuntimeError::DivisionByZero)
                 } else {
                     Ok(Self::Float(b.as_float().unwrap() % f))
                 }
             }
             (Self::Int(i), Self::Bool(b)) => {
                 if !b {
-                    Err(DivisionError::DivisionByZero)
+                    Err(RuntimeError::DivisionByZero)
                 } else {
                     Ok(Self::Int(i % Self::Bool(b).as_int().unwrap()))
                 }
             }
             (Self::Float(f), Self::Bool(b)) => {
                 if !b {
-                    Err(DivisionError::DivisionByZero)
+                    Err(RuntimeError::DivisionByZero)
                 } else {
                     Ok(Self::Float(f % Self::Bool(b).as_float().unwrap()))
                 }
             }
 
-            (s @ Self::Str(_), other) => Err(DivisionError::IncompatibleTypes(
-                OperationIncompatibleTypesError {
-                    operation: "modulo".to_string(),
-                    a: s,
-                    b: Some(other),
-                },
+            // strings, lists, and all the other combinations of numbers, which are handled by `Numbers`
+            (a, b) => Err(RuntimeError::IncompatibleTypes {
+                operation: "modulo".to_string(),
+                a,
+                b: Some(b),
+            }),
+        }
+    }
+}
+
+// the bitwise operations only work on integers, and return a big integer if either operand is one
+impl Value {
+    fn bitwise(
+        self,
+        other: Self,
+        operation: &str,
+        int: fn(i64, i64) -> i64,
+        big: fn(BigInt, BigInt) -> BigInt,
+    ) -> Result<Self, RuntimeError> {
+        match (self, other) {
+            (Self::Int(a), Self::Int(b)) => Ok(Self::Int(int(a, b))),
+            (a @ (Self::Int(_) | Self::BigInt(_)), b @ (Self::Int(_) | Self::BigInt(_))) => Ok(
+                Self::BigInt(big(a.as_bigint().unwrap(), b.as_bigint().unwrap())),
+            ),
+            (a, b) => Err(RuntimeError::IncompatibleTypes {
+                operation: operation.to_string(),
+                a,
+                b: Some(b),
+            }),
+        }
+    }
+
+    pub fn bit_and(self, other: Self) -> Result<Self, RuntimeError> {
+        self.bitwise(other, "compute bitwise AND of", |a, b| a & b, |a, b| a & b)
+    }
+
+    pub fn bit_or(self, other: Self) -> Result<Self, RuntimeError> {
+        self.bitwise(other, "compute bitwise OR of", |a, b| a | b, |a, b| a | b)
+    }
+
+    pub fn bit_xor(self, other: Self) -> Result<Self, RuntimeError> {
+        self.bitwise(other, "compute bitwise XOR of", |a, b| a ^ b, |a, b| a ^ b)
+    }
+
+    pub fn bit_not(self) -> Result<Self, RuntimeError> {
+        match self {
+            Self::Int(value) => Ok(Self::Int(!value)),
+            Self::BigInt(value) => Ok(Self::BigInt(!value)),
+            _ => Err(RuntimeError::IncompatibleTypes {
+                operation: "compute bitwise NOT of".to_string(),
+                a: self,
+                b: None,
+            }),
+        }
+    }
+
+    // shifts to the left, or to the right if `amount` is negative
+    //
+    // bits shifted out of an int are lost, and shifting to the right keeps the sign
+    pub fn shift_left(self, amount: i64) -> Result<Self, RuntimeError> {
+        let distance = amount.unsigned_abs().min(u32::MAX as u64) as u32;
+
+        match self {
+            Self::Int(value) if amount >= 0 => {
+                Ok(Self::Int(value.checked_shl(distance).unwrap_or(0)))
+            }
+            Self::Int(value) => Ok(Self::Int(
+                value
+                    .checked_shr(distance)
+                    .unwrap_or(if value < 0 { -1 } else { 0 }),
             )),
-            (other, s @ Self::Str(_)) => Err(DivisionError::IncompatibleTypes(
-                OperationIncompatibleTypesError {
-                    operation: "modulo".to_string(),
-                    a: other,
-                    b: Some(s),
+            Self::BigInt(value) if amount >= 0 => Ok(Self::BigInt(value << distance)),
+            Self::BigInt(value) => Ok(Self::BigInt(value >> distance)),
+            _ => Err(RuntimeError::IncompatibleTypes {
+                operation: "shift".to_string(),
+                a: self,
+                b: None,
+            }),
+        }
+    }
+}
+
+impl Not for Value {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        Self::Bool(!self.as_bool())
+    }
+}
+
+// values are equal if they have the same type and value, except that numbers are equal to numbers of any type with
+// the same value, like they're ordered, since big integers and decimals are often the result of operations on ints
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        if is_number(self) && is_number(other) {
+            return compare_numbers(self, other) == Some(Ordering::Equal);
+        }
+
+        match (self, other) {
+            (Self::Null, Self::Null) => true,
+            (Self::Bool(a), Self::Bool(b)) => a == b,
+            (Self::Str(a), Self::Str(b)) => a == b,
+            (Self::List(a), Self::List(b)) => a == b,
+            (
+                Self::Struct { name, fields },
+                Self::Struct {
+                    name: other_name,
+                    fields: other_fields,
                 },
-            )),
+            ) => name == other_name && fields == other_fields,
+            (Self::Enum(a), Self::Enum(b)) => a == b,
+            (Self::Node(a), Self::Node(b)) => a == b,
+            _ => false,
         }
     }
 }
 
 impl PartialOrd for Value {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let numeric = |value: &Value| is_number(value) || matches!(value, Value::Bool(_));
+
+        if numeric(self) && numeric(other) {
+            return compare_numbers(self, other);
+        }
+
         match (self, other) {
             (Self::Null, Self::Null) => Some(Ordering::Equal),
             (Self::Null, _) | (_, Self::Null) => None,
 
-            (Self::Int(a), Self::Int(b)) => a.partial_cmp(b),
-            (Self::Float(a), Self::Float(b)) => a.partial_cmp(b),
-
-            (Self::Int(i), Self::Float(f)) => (*i as f64).partial_cmp(f),
-            (Self::Float(f), Self::Int(i)) => f.partial_cmp(&(*i as f64)),
-
-            (Self::Bool(a), Self::Bool(b)) => a.partial_cmp(b),
-
-            (b @ Self::Bool(_), Self::Int(i)) => b.as_int().unwrap().partial_cmp(i),
-            (Self::Int(i), b @ Self::Bool(_)) => i.partial_cmp(&b.as_int().unwrap()),
-            (b @ Self::Bool(_), Self::Float(f)) => b.as_float().unwrap().partial_cmp(f),
-            (Self::Float(f), b @ Self::Bool(_)) => f.partial_cmp(&b.as_float().unwrap()),
-
             (Self::Str(a), Self::Str(b)) => a.partial_cmp(b),
             (_, Self::Str(_)) | (Self::Str(_), _) => None,
+
+            (Self::List(a), Self::List(b)) => a.partial_cmp(b),
+            (_, Self::List(_)) | (Self::List(_), _) => None,
+
+            // structs, enums and nodes have no order
+            _ => None,
         }
     }
 }
@@ -0,0 +1,73 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{error::RuntimeError, value::Value};
+
+// the variables of the program or of a function call, with the ones that are constants
+//
+// the constants are kept with the variables they belong to, so a function, which has its own copy of the variables, can
+// assign to the ones that are constants outside of it, and the ones it defines go away when it returns
+#[derive(Debug, Default)]
+pub struct Variables {
+    values: HashMap<String, Value>,
+    // the variables defined with <const> or frozen with <freeze>, which can't be assigned to
+    constants: HashSet<String>,
+}
+
+impl Variables {
+    // the copy of the variables a function call works on, where none of them are constants yet, since assigning to
+    // them only changes the copy
+    pub fn for_call(&self) -> Self {
+        Self {
+            values: self.values.clone(),
+            constants: HashSet::new(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.values.get(name)
+    }
+
+    // `Err` if the variable is a constant, so it can't be changed in place either
+    pub fn get_mut(&mut self, name: &str) -> Result<Option<&mut Value>, RuntimeError> {
+        if self.constants.contains(name) {
+            return Err(RuntimeError::ConstantReassignment(name.to_string()));
+        }
+
+        Ok(self.values.get_mut(name))
+    }
+
+    // stores a value in a variable, unless the variable is a constant
+    pub fn set(&mut self, name: &str, value: Value) -> Result<(), RuntimeError> {
+        if self.constants.contains(name) {
+            return Err(RuntimeError::ConstantReassignment(name.to_string()));
+        }
+
+        self.values.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    pub fn set_constant(&mut self, name: &str, value: Value) -> Result<(), RuntimeError> {
+        self.set(name, value)?;
+        self.constants.insert(name.to_string());
+        Ok(())
+    }
+
+    // makes an existing variable a constant, returning its value
+    pub fn freeze(&mut self, name: &str) -> Result<&Value, RuntimeError> {
+        let value = self
+            .values
+            .get(name)
+            .ok_or_else(|| RuntimeError::UndefinedVariable(name.to_string()))?;
+        self.constants.insert(name.to_string());
+        Ok(value)
+    }
+
+    // runs code whose constants go away when it's done, like an iteration of a loop, which defines them again on the
+    // next one; the variables themselves stay, since blocks don't have their own scope
+    pub fn scoped<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        let constants = self.constants.clone();
+        let result = f(self);
+        self.constants = constants;
+        result
+    }
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use common::{assert_prints, error, run_with};
+
+#[test]
+fn big_integers_have_no_size_limit() {
+    assert_prints(&[
+        (
+            "<bigint>123456789012345678901234567890</bigint>",
+            "123456789012345678901234567890",
+        ),
+        (
+            "<mul><bigint>9223372036854775807</bigint><int>2</int></mul>",
+            "18446744073709551614",
+        ),
+        (
+            "<type><add><bigint>1</bigint><int>1</int></add></type>",
+            "bigint",
+        ),
+        ("<bigint><float>2.9</float></bigint>", "2"),
+    ]);
+}
+
+#[test]
+fn decimals_are_exact() {
+    assert_prints(&[
+        (
+            "<add><decimal>0.1</decimal><decimal>0.2</decimal></add>",
+            "0.3",
+        ),
+        (
+            "<add><float>0.1</float><float>0.2</float></add>",
+            "0.30000000000000004",
+        ),
+        ("<mul><decimal>1.10</decimal><int>2</int></mul>", "2.20"),
+        (
+            "<div><decimal>10</decimal><int>3</int></div>",
+            "3.3333333333333333333333333333",
+        ),
+        ("<decimal>1.5e3</decimal>", "1500"),
+        (
+            "<type><add><decimal>1</decimal><bigint>1</bigint></add></type>",
+            "decimal",
+        ),
+        (
+            "<type><add><decimal>1</decimal><float>1</float></add></type>",
+            "float",
+        ),
+    ]);
+}
+
+#[test]
+fn big_integers_equal_ints_with_the_same_value() {
+    assert_prints(&[
+        ("<eq><bigint>5</bigint><int>5</int></eq>", "true"),
+        ("<ne><bigint>5</bigint><int>5</int></ne>", "false"),
+        ("<le><bigint>5</bigint><int>5</int></le>", "true"),
+        ("<eq><bigint>5</bigint><int>6</int></eq>", "false"),
+        ("<eq><decimal>2.50</decimal><float>2.5</float></eq>", "true"),
+        ("<eq><decimal>2</decimal><bigint>2</bigint></eq>", "true"),
+        (
+            "<eq><list><bigint>1</bigint></list><list><int>1</int></list></eq>",
+            "true",
+        ),
+        ("<eq><bigint>1</bigint><true /></eq>", "false"),
+    ]);
+}
+
+#[test]
+fn numbers_of_any_type_are_equal_by_exact_value() {
+    assert_prints(&[
+        ("<eq><int>1</int><bigint>1</bigint></eq>", "true"),
+        ("<eq><bigint>1</bigint><float>1.0</float></eq>", "true"),
+        ("<eq><int>1</int><float>1.0</float></eq>", "true"),
+        (
+            "<eq><int>1</int><bigint>1</bigint><float>1.0</float><decimal>1.00</decimal></eq>",
+            "true",
+        ),
+        ("<eq><float>0.1</float><decimal>0.1</decimal></eq>", "false"),
+        ("<eq><decimal>0.5</decimal><float>0.5</float></eq>", "true"),
+        // 2^53 + 1 has no float, so it isn't equal to the float it rounds to, unlike 2^53 itself
+        (
+            "<eq><int>9007199254740993</int><float>9007199254740992</float></eq>",
+            "false",
+        ),
+        (
+            "<eq><int>9007199254740992</int><float>9007199254740992</float></eq>",
+            "true",
+        ),
+        (
+            "<lt><int>9007199254740992</int><float>9007199254740993</float></lt>",
+            "false",
+        ),
+        (
+            "<lt><int>9007199254740993</int><bigint>9007199254740994</bigint></lt>",
+            "true",
+        ),
+        (
+            "<gt><float>inf</float><bigint>99999999999999999999999</bigint></gt>",
+            "true",
+        ),
+        ("<eq><float>nan</float><int>0</int></eq>", "false"),
+        ("<lt><true /><decimal>1.5</decimal></lt>", "true"),
+    ]);
+}
+
+#[test]
+fn promoted_results_equal_plain_ints() {
+    let printed = run_with(
+        r#"<program>
+            <set var="x"><sub><add><int>9223372036854775807</int><int>1</int></add><int>1</int></sub></set>
+            <print><type><get var="x" /></type></print>
+            <print><eq><get var="x" /><int>9223372036854775807</int></eq></print>
+        </program>"#,
+        &["--overflow", "promote"],
+        "",
+    )
+    .ok();
+
+    assert_eq!(printed, "bigint\ntrue\n");
+}
+
+#[test]
+fn decimal_overflow_is_reported() {
+    error(
+        "<mul><decimal>79228162514264337593543950335</decimal><int>2</int></mul>",
+        "xmlang::decimal_overflow",
+    );
+}
+
+#[test]
+fn division_by_zero_is_reported() {
+    error(
+        "<div><bigint>1</bigint><int>0</int></div>",
+        "xmlang::division_by_zero",
+    );
+    error(
+        "<mod><decimal>1</decimal><decimal>0</decimal></mod>",
+        "xmlang::division_by_zero",
+    );
+}
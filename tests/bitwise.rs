@@ -0,0 +1,105 @@
+mod common;
+
+use common::{assert_prints, error};
+
+#[test]
+fn bitwise_operations_fold_their_children() {
+    assert_prints(&[
+        ("<bit-and><int>12</int><int>10</int></bit-and>", "8"),
+        ("<bit-or><int>12</int><int>10</int></bit-or>", "14"),
+        ("<bit-xor><int>12</int><int>10</int></bit-xor>", "6"),
+        ("<bit-or><int>1</int><int>2</int><int>4</int></bit-or>", "7"),
+        ("<bit-not><int>0</int></bit-not>", "-1"),
+    ]);
+}
+
+#[test]
+fn bitwise_operations_on_big_integers_return_big_integers() {
+    assert_prints(&[
+        (
+            "<type><bit-and><bigint>12</bigint><int>10</int></bit-and></type>",
+            "bigint",
+        ),
+        ("<bit-not><bigint>5</bigint></bit-not>", "-6"),
+    ]);
+}
+
+#[test]
+fn shifts() {
+    assert_prints(&[
+        (r#"<shl by="4"><int>1</int></shl>"#, "16"),
+        (r#"<shr by="1"><int>-8</int></shr>"#, "-4"),
+        (r#"<shl by="-1"><int>8</int></shl>"#, "4"),
+        (r#"<shl by="64"><int>1</int></shl>"#, "0"),
+        (r#"<shr by="100"><int>-1</int></shr>"#, "-1"),
+        (
+            r#"<shl by="100"><bigint>1</bigint></shl>"#,
+            "1267650600228229401496703205376",
+        ),
+        ("<shl><int>1</int><int>3</int></shl>", "8"),
+    ]);
+}
+
+#[test]
+fn bitwise_operations_only_accept_integers() {
+    error(
+        "<bit-and><float>1.5</float><int>1</int></bit-and>",
+        "xmlang::type_mismatch",
+    );
+    error(
+        "<bit-not><string>1</string></bit-not>",
+        "xmlang::type_mismatch",
+    );
+}
+
+#[test]
+fn parsing_other_bases() {
+    assert_prints(&[
+        (r#"<int base="16">ff</int>"#, "255"),
+        (r#"<int base="16">0xFF</int>"#, "255"),
+        (r#"<int base="2">-0b1010</int>"#, "-10"),
+        (r#"<int base="36">z</int>"#, "35"),
+        (
+            r#"<bigint base="16">ffffffffffffffffffff</bigint>"#,
+            "1208925819614629174706175",
+        ),
+    ]);
+
+    error(r#"<int base="2">102</int>"#, "xmlang::conversion_failed");
+    error(r#"<int base="37">1</int>"#, "xmlang::invalid_attribute");
+}
+
+#[test]
+fn writing_other_bases() {
+    assert_prints(&[
+        (
+            r#"<to-string base="2" prefix="true"><int>10</int></to-string>"#,
+            "0b1010",
+        ),
+        (
+            r#"<to-string base="16" uppercase="true"><int>-255</int></to-string>"#,
+            "-FF",
+        ),
+        (
+            r#"<to-string base="36"><bigint>35</bigint></to-string>"#,
+            "z",
+        ),
+        (
+            r#"<to-string base="2"><string>255</string></to-string>"#,
+            "11111111",
+        ),
+        (
+            r#"<to-string base="16"><string>-99999999999999999999</string></to-string>"#,
+            "-56bc75e2d630fffff",
+        ),
+    ]);
+
+    error(
+        r#"<to-string base="2"><string>ff</string></to-string>"#,
+        "xmlang::conversion_failed",
+    );
+    error(
+        r#"<to-string base="2"><float>1.5</float></to-string>"#,
+        "xmlang::conversion_failed",
+    );
+}
@@ -0,0 +1,114 @@
+// runs XMLang programs with the built interpreter, for the integration tests in this directory
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+static PROGRAMS: AtomicUsize = AtomicUsize::new(0);
+
+pub struct Output {
+    pub stdout: String,
+    pub stderr: String,
+    pub success: bool,
+}
+
+impl Output {
+    // fails the test with the error of the program if it didn't run successfully
+    #[track_caller]
+    pub fn ok(self) -> String {
+        assert!(self.success, "the program failed:\n{}", self.stderr);
+        self.stdout
+    }
+
+    // fails the test if the program ran successfully, or if it failed with a different error code
+    #[track_caller]
+    pub fn err(self, code: &str) -> String {
+        assert!(
+            !self.success,
+            "the program should have failed, but printed:\n{}",
+            self.stdout
+        );
+        assert!(
+            self.stderr.contains(code),
+            "the program should have failed with `{code}`, but failed with:\n{}",
+            self.stderr
+        );
+        self.stderr
+    }
+}
+
+// writes the program to a temporary file and runs it with the given command line options and standard input
+pub fn run_with(source: &str, args: &[&str], stdin: &str) -> Output {
+    let path = temp_path();
+    fs::write(&path, source).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_xmlang"))
+        .arg(&path)
+        .args(args)
+        .env("NO_COLOR", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    fs::remove_file(&path).unwrap();
+
+    Output {
+        stdout: String::from_utf8(output.stdout).unwrap(),
+        stderr: String::from_utf8(output.stderr).unwrap(),
+        success: output.status.success(),
+    }
+}
+
+pub fn run(source: &str) -> Output {
+    run_with(source, &[], "")
+}
+
+// wraps the elements in a <program>, runs it, and returns what it printed
+#[track_caller]
+pub fn output(body: &str) -> String {
+    run(&format!("<program>{body}</program>")).ok()
+}
+
+// wraps the elements in a <program>, runs it, and returns the error it failed with, which must have the given code
+#[track_caller]
+pub fn error(body: &str, code: &str) -> String {
+    run(&format!("<program>{body}</program>")).err(code)
+}
+
+// prints each expression on its own line, and checks what each of them printed
+#[track_caller]
+pub fn assert_prints(cases: &[(&str, &str)]) {
+    let body = cases
+        .iter()
+        .map(|(expression, _)| format!("<print>{expression}</print>"))
+        .collect::<String>();
+    let printed = output(&body);
+
+    for ((expression, expected), actual) in cases.iter().zip(printed.lines()) {
+        assert_eq!(actual, *expected, "{expression}");
+    }
+    assert_eq!(printed.lines().count(), cases.len());
+}
+
+fn temp_path() -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "xmlang-test-{}-{}.xml",
+        std::process::id(),
+        PROGRAMS.fetch_add(1, Ordering::Relaxed)
+    ))
+}
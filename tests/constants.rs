@@ -0,0 +1,151 @@
+mod common;
+
+use common::{error, output, run};
+
+#[test]
+fn constants_can_be_read() {
+    assert_eq!(
+        output(
+            r#"<const var="MAX"><int>4</int></const>
+            <print><add><get var="MAX" /><int>1</int></add></print>"#
+        ),
+        "5\n"
+    );
+}
+
+#[test]
+fn every_kind_of_assignment_is_rejected() {
+    for assignment in [
+        r#"<set var="MAX"><int>5</int></set>"#,
+        r#"<const var="MAX"><int>5</int></const>"#,
+        r#"<inc var="MAX" />"#,
+        r#"<dec var="MAX" />"#,
+        r#"<update var="MAX" op="add"><int>1</int></update>"#,
+        r#"<loop var="MAX" end="1" />"#,
+    ] {
+        let result = run(&format!(
+            r#"<program>
+                <const var="MAX"><int>4</int></const>
+                <print>started</print>
+                {assignment}
+            </program>"#
+        ));
+        assert_eq!(result.stdout, "", "{assignment}");
+        result.err("xmlang::constant_reassignment");
+    }
+}
+
+#[test]
+fn assignments_that_may_not_happen_fail_at_runtime() {
+    assert_eq!(
+        output(
+            r#"<const var="MAX"><int>4</int></const>
+            <try>
+                <do><eval><quote><set var="MAX"><int>0</int></set></quote></eval></do>
+                <catch kind="ConstantReassignment"><print><special name="error" /></print></catch>
+            </try>
+            <print><get var="MAX" /></print>"#
+        ),
+        "Can't assign to `MAX`, because it's a constant\n4\n"
+    );
+}
+
+#[test]
+fn functions_can_assign_to_their_copy_of_a_constant() {
+    assert_eq!(
+        output(
+            r#"<const var="MAX"><int>4</int></const>
+            <function name="reset"><set var="MAX"><int>0</int></set><get var="MAX" /></function>
+            <print><reset /></print>
+            <print><get var="MAX" /></print>"#
+        ),
+        "0\n4\n"
+    );
+}
+
+#[test]
+fn constants_in_a_loop_last_until_the_end_of_the_iteration() {
+    assert_eq!(
+        output(
+            r#"<loop start="0" end="2">
+                <const var="doubled"><mul><special name="iteration" /><int>2</int></mul></const>
+                <print><get var="doubled" /></print>
+            </loop>
+            <set var="n"><int>0</int></set>
+            <while>
+                <condition><lt><get var="n" /><int>2</int></lt></condition>
+                <do>
+                    <const var="next"><add><get var="n" /><int>1</int></add></const>
+                    <set var="n"><get var="next" /></set>
+                </do>
+            </while>
+            <set var="doubled"><int>0</int></set>
+            <print><get var="n" /></print>"#
+        ),
+        "0\n2\n2\n"
+    );
+}
+
+#[test]
+fn running_const_twice_in_the_same_scope_is_an_error() {
+    let result = run(r#"<program>
+            <function name="define"><const var="ONCE"><int>1</int></const></function>
+            <loop start="0" end="2">
+                <print><special name="iteration" /></print>
+                <block><const var="ONCE"><int>1</int></const></block>
+            </loop>
+            <print>after the loop</print>
+            <const var="TWICE"><int>1</int></const>
+            <block><define /></block>
+            <eval><quote><const var="TWICE"><int>2</int></const></quote></eval>
+        </program>"#);
+    assert_eq!(result.stdout, "0\n1\nafter the loop\n");
+    result.err("xmlang::constant_reassignment");
+}
+
+#[test]
+fn constants_in_functions_last_until_they_return() {
+    assert_eq!(
+        output(
+            r#"<function name="f">
+                <const var="local"><int>1</int></const>
+                <get var="local" />
+            </function>
+            <print><f /></print>
+            <print><f /></print>"#
+        ),
+        "1\n1\n"
+    );
+}
+
+#[test]
+fn freeze_makes_a_variable_constant() {
+    assert_eq!(
+        output(
+            r#"<set var="players"><list><string>Ann</string></list></set>
+            <update var="players" op="add"><string>Bob</string></update>
+            <print><freeze var="players" /></print>
+            <try>
+                <do><update var="players" op="add"><string>Cid</string></update></do>
+                <catch><print><special name="error_kind" /></print></catch>
+            </try>
+            <print><length><get var="players" /></length></print>"#
+        ),
+        "[Ann, Bob]\nConstantReassignment\n2\n"
+    );
+}
+
+#[test]
+fn freezing_an_undefined_variable_is_an_error() {
+    error(r#"<freeze var="missing" />"#, "xmlang::undefined_variable");
+}
+
+#[test]
+fn set_field_on_a_constant_is_an_error() {
+    error(
+        r#"<struct name="Point"><field name="x" /></struct>
+        <const var="origin"><new type="Point" x="0" /></const>
+        <set-field var="origin" name="x"><int>1</int></set-field>"#,
+        "xmlang::constant_reassignment",
+    );
+}
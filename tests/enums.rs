@@ -0,0 +1,124 @@
+mod common;
+
+use common::{error, output, run};
+
+const SHAPE: &str = r#"<enum name="Shape">
+    <variant name="Circle"><field name="radius" type="float" /></variant>
+    <variant name="Rectangle">
+        <field name="width" type="float" />
+        <field name="height" type="float"><float>1</float></field>
+    </variant>
+    <variant name="Nothing" />
+</enum>"#;
+
+fn with_shape(body: &str) -> String {
+    output(&format!("{SHAPE}{body}"))
+}
+
+#[test]
+fn variants_are_created_with_new() {
+    assert_eq!(
+        with_shape(
+            r#"<print><new type="Shape" variant="Circle" radius="2" /></print>
+            <print><new type="Shape" variant="Rectangle" width="3" /></print>
+            <print><new type="Shape" variant="Nothing" /></print>
+            <print><type><new type="Shape" variant="Nothing" /></type></print>"#
+        ),
+        "Circle { radius: 2 }\nRectangle { width: 3, height: 1 }\nNothing\nShape\n"
+    );
+}
+
+#[test]
+fn fields_can_be_read_and_changed() {
+    assert_eq!(
+        with_shape(
+            r#"<set var="shape"><new type="Shape" variant="Circle" radius="2" /></set>
+            <set-field var="shape" name="radius"><int>5</int></set-field>
+            <print><get-field name="radius"><get var="shape" /></get-field></print>"#
+        ),
+        "5\n"
+    );
+    error(
+        &format!(
+            r#"{SHAPE}<get-field name="radius"><new type="Shape" variant="Nothing" /></get-field>"#
+        ),
+        "xmlang::undefined_field",
+    );
+}
+
+#[test]
+fn enums_are_equal_when_variant_and_fields_are() {
+    assert_eq!(
+        with_shape(
+            r#"<print><eq><new type="Shape" variant="Circle" radius="1" /><new type="Shape" variant="Circle" radius="1" /></eq></print>
+            <print><eq><new type="Shape" variant="Circle" radius="1" /><new type="Shape" variant="Circle" radius="2" /></eq></print>
+            <print><eq><new type="Shape" variant="Nothing" /><new type="Shape" variant="Circle" radius="1" /></eq></print>"#
+        ),
+        "true\nfalse\nfalse\n"
+    );
+}
+
+#[test]
+fn match_handles_every_variant() {
+    assert_eq!(
+        with_shape(
+            r#"<function name="area">
+                <match>
+                    <special name="child:0" />
+                    <case variant="Circle"><mul><special name="radius" /><special name="radius" /><int>3</int></mul></case>
+                    <case variant="Rectangle"><mul><special name="width" /><special name="height" /></mul></case>
+                    <case variant="Nothing"><int>0</int></case>
+                </match>
+            </function>
+            <print><area><new type="Shape" variant="Circle" radius="2" /></area></print>
+            <print><area><new type="Shape" variant="Rectangle" width="2" height="4" /></area></print>
+            <print><area><new type="Shape" variant="Nothing" /></area></print>"#
+        ),
+        "12\n8\n0\n"
+    );
+}
+
+#[test]
+fn missing_variants_are_reported_before_running() {
+    let result = run(&format!(
+        r#"<program>
+            {SHAPE}
+            <print>started</print>
+            <match>
+                <new type="Shape" variant="Nothing" />
+                <case variant="Circle">circle</case>
+                <case variant="Nothing">nothing</case>
+            </match>
+        </program>"#
+    ));
+    assert_eq!(result.stdout, "");
+    let stderr = result.err("xmlang::non_exhaustive_match");
+    assert!(stderr.contains("`Rectangle`"), "{stderr}");
+}
+
+#[test]
+fn invalid_variants_are_errors() {
+    error(
+        &format!(r#"{SHAPE}<new type="Shape" variant="Triangle" />"#),
+        "xmlang::undefined_variant",
+    );
+    error(
+        &format!(r#"{SHAPE}<new type="Shape" variant="Circle" />"#),
+        "xmlang::missing_field",
+    );
+    error(r#"<enum name="Empty" />"#, "xmlang::invalid_children");
+}
+
+#[test]
+fn enums_cant_be_used_in_arithmetic() {
+    for operand in ["<true />", "<int>1</int>"] {
+        error(
+            &format!(r#"{SHAPE}<mul><new type="Shape" variant="Nothing" />{operand}</mul>"#),
+            "xmlang::type_mismatch",
+        );
+        error(
+            &format!(r#"{SHAPE}<add>{operand}<new type="Shape" variant="Nothing" /></add>"#),
+            "xmlang::type_mismatch",
+        );
+    }
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use common::{error, run};
+
+#[test]
+fn built_in_errors_have_stable_codes() {
+    let cases = [
+        ("<frobnicate />", "xmlang::unknown_element"),
+        (r#"<get-field field="x" />"#, "xmlang::missing_attribute"),
+        ("<int>abc</int>", "xmlang::conversion_failed"),
+        (
+            "<sub><string>a</string><int>1</int></sub>",
+            "xmlang::type_mismatch",
+        ),
+        (
+            "<div><int>1</int><int>0</int></div>",
+            "xmlang::division_by_zero",
+        ),
+        (r#"<call name="nope" />"#, "xmlang::undefined_function"),
+        (r#"<special name="nope" />"#, "xmlang::undefined_special"),
+        (r#"<rand min="2" max="1" />"#, "xmlang::invalid_range"),
+        ("<throw>oops</throw>", "xmlang::thrown"),
+    ];
+
+    for (body, code) in cases {
+        error(body, code);
+    }
+}
+
+#[test]
+fn root_must_be_a_program() {
+    run("<block />").err("xmlang::invalid_root");
+}
+
+#[test]
+fn errors_point_at_the_element_that_raised_them() {
+    let stderr = error(
+        "\n<print>\n    <div><int>1</int><int>0</int></div>\n</print>\n",
+        "xmlang::division_by_zero",
+    );
+
+    assert!(stderr.contains(":3:5]"), "wrong location:\n{stderr}");
+}
+
+#[test]
+fn catch_sees_the_kind_of_built_in_errors() {
+    let stderr = error(
+        r#"<try>
+            <do><sub><string>a</string><int>1</int></sub></do>
+            <catch kind="IncompatibleTypes"><throw><special name="error_kind" /></throw></catch>
+        </try>"#,
+        "xmlang::thrown",
+    );
+
+    assert!(stderr.contains("IncompatibleTypes"));
+}
@@ -0,0 +1,152 @@
+mod common;
+
+use common::{error, output};
+
+#[test]
+fn catch_filters_by_kind() {
+    assert_eq!(
+        output(
+            r#"<try>
+                <do><throw kind="NotFound">missing</throw></do>
+                <catch kind="DivisionByZero"><print>wrong</print></catch>
+                <catch kind="Timeout NotFound"><print><special name="error_kind" /></print></catch>
+                <catch><print>too late</print></catch>
+            </try>"#
+        ),
+        "NotFound\n"
+    );
+}
+
+#[test]
+fn catch_filters_by_code() {
+    assert_eq!(
+        output(
+            r#"<try>
+                <do><div><int>1</int><int>0</int></div></do>
+                <catch kind="xmlang::division_by_zero"><print><special name="error_code" /></print></catch>
+            </try>"#
+        ),
+        "xmlang::division_by_zero\n"
+    );
+}
+
+#[test]
+fn unmatched_errors_keep_propagating() {
+    error(
+        r#"<try>
+            <do><div><int>1</int><int>0</int></div></do>
+            <catch kind="NotFound"><print>wrong</print></catch>
+        </try>"#,
+        "xmlang::division_by_zero",
+    );
+}
+
+#[test]
+fn thrown_errors_carry_their_payload() {
+    assert_eq!(
+        output(
+            r#"<try>
+                <do>
+                    <throw kind="NotFound">
+                        <data><list><int>404</int><string>/index.html</string></list></data>
+                        Page not found
+                    </throw>
+                </do>
+                <catch>
+                    <print><special name="error" /></print>
+                    <print><special name="error_data" /></print>
+                    <print><special name="error_code" /></print>
+                </catch>
+            </try>"#
+        ),
+        "Page not found\n[404, /index.html]\nxmlang::thrown\n"
+    );
+}
+
+#[test]
+fn finally_always_runs() {
+    assert_eq!(
+        output(
+            r#"<try>
+                <do><print>do</print></do>
+                <finally><print>finally</print></finally>
+            </try>
+            <try>
+                <do><throw>oops</throw></do>
+                <catch><print>catch</print></catch>
+                <finally><print>finally</print></finally>
+            </try>"#
+        ),
+        "do\nfinally\ncatch\nfinally\n"
+    );
+
+    let stderr = error(
+        r#"<try>
+            <do><throw kind="Oops">oops</throw></do>
+            <finally><print>finally</print></finally>
+        </try>"#,
+        "xmlang::thrown",
+    );
+    assert!(stderr.contains("oops"));
+}
+
+#[test]
+fn rethrow_keeps_the_kind_and_payload_of_thrown_errors() {
+    assert_eq!(
+        output(
+            r#"<try>
+                <do>
+                    <try>
+                        <do><throw kind="NotFound"><data><int>404</int></data>missing</throw></do>
+                        <catch><print>inner</print><rethrow /></catch>
+                    </try>
+                </do>
+                <catch kind="NotFound">
+                    <print><special name="error" /></print>
+                    <print><special name="error_data" /></print>
+                </catch>
+            </try>"#
+        ),
+        "inner\nmissing\n404\n"
+    );
+}
+
+#[test]
+fn rethrow_keeps_built_in_errors_as_they_are() {
+    assert_eq!(
+        output(
+            r#"<try>
+                <do>
+                    <try>
+                        <do><div><int>1</int><int>0</int></div></do>
+                        <catch><rethrow /></catch>
+                    </try>
+                </do>
+                <catch kind="xmlang::division_by_zero">
+                    <print><special name="error_kind" /></print>
+                    <print><special name="error_code" /></print>
+                </catch>
+            </try>"#
+        ),
+        "DivisionByZero\nxmlang::division_by_zero\n"
+    );
+}
+
+#[test]
+fn rethrow_keeps_the_stack_trace() {
+    let stderr = error(
+        r#"<function name="f"><div><int>1</int><int>0</int></div></function>
+        <try>
+            <do><call name="f" /></do>
+            <catch><rethrow /></catch>
+        </try>"#,
+        "xmlang::division_by_zero",
+    );
+
+    assert!(stderr.contains("in f()"));
+}
+
+#[test]
+fn rethrow_outside_catch_fails() {
+    error("<rethrow />", "xmlang::rethrow_outside_catch");
+}
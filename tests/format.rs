@@ -0,0 +1,96 @@
+mod common;
+
+use common::{error, output};
+
+#[test]
+fn placeholders_insert_variables_and_children() {
+    assert_eq!(
+        output(
+            r#"<set var="name"><string>Alice</string></set>
+            <set var="age"><int>42</int></set>
+            <print><format>Hello, {name}! You are {age} years old.</format></print>
+            <print><format>{0} + {1} = {2}<int>1</int><int>2</int><int>3</int></format></print>"#
+        ),
+        "Hello, Alice! You are 42 years old.\n1 + 2 = 3\n"
+    );
+}
+
+#[test]
+fn placeholders_insert_specials() {
+    assert_eq!(
+        output(
+            r#"<function name="greet"><format>Hi, {person} and {child:0}!</format></function>
+            <print><call name="greet" person="Bob"><string>Carol</string></call></print>"#
+        ),
+        "Hi, Bob and Carol!\n"
+    );
+}
+
+#[test]
+fn specs_control_width_alignment_and_precision() {
+    assert_eq!(
+        output(
+            r#"<set var="name"><string>Alice</string></set>
+            <set var="age"><int>42</int></set>
+            <print><format>[{name:>8}] [{name:*^9}] [{name:.3}]</format></print>
+            <print><format>{age:05} {age:+} {0:.2} {0:e}<float>1.5</float></format></print>"#
+        ),
+        "[   Alice] [**Alice**] [Ali]\n00042 +42 1.50 1.5e0\n"
+    );
+}
+
+#[test]
+fn specs_write_numbers_in_other_bases() {
+    assert_eq!(
+        output(r#"<print><format>{0:x} {0:#X} {0:o} {0:#b}<int>42</int></format></print>"#),
+        "2a 0x2A 52 0b101010\n"
+    );
+}
+
+#[test]
+fn doubled_braces_are_literal() {
+    assert_eq!(
+        output(r#"<set var="x"><int>1</int></set><print><format>{{{x}}}</format></print>"#),
+        "{1}\n"
+    );
+}
+
+#[test]
+fn text_next_to_children_keeps_its_spaces() {
+    assert_eq!(
+        output(
+            r#"<print><format>Sum: <add><int>1</int><int>2</int></add> is {0}</format></print>"#
+        ),
+        "Sum:  is 3\n"
+    );
+}
+
+#[test]
+fn indentation_around_the_template_is_ignored() {
+    assert_eq!(
+        output(
+            r#"<print>
+                <format>
+                    Hello, {0}!
+                    <string>world</string>
+                </format>
+            </print>"#
+        ),
+        "Hello, world!\n"
+    );
+}
+
+#[test]
+fn unknown_placeholders_are_reported() {
+    error("<format>{nope}</format>", "xmlang::undefined_placeholder");
+}
+
+#[test]
+fn invalid_templates_are_reported() {
+    error("<format>{0</format>", "xmlang::invalid_format");
+    error("<format>}</format>", "xmlang::invalid_format");
+    error(
+        "<format>{0:?}<int>1</int></format>",
+        "xmlang::invalid_format",
+    );
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use common::{error, output, run};
+
+const GREET: &str = r#"<function name="greet">
+    <print>
+        <if>
+            <condition><eq><special name="child_count" /><int>0</int></eq></condition>
+            <then><format>Hello, {person}!</format></then>
+            <else><format>Hello, {0}! ({1} child)<special name="child:0" /><special name="child_count" /></format></else>
+        </if>
+    </print>
+</function>"#;
+
+#[test]
+fn functions_can_be_called_as_elements() {
+    assert_eq!(
+        output(&format!(
+            r#"{GREET}
+            <call name="greet" person="Alice" />
+            <greet person="Bob" />
+            <greet><string>Carol</string></greet>"#
+        )),
+        "Hello, Alice!\nHello, Bob!\nHello, Carol! (1 child)\n"
+    );
+}
+
+#[test]
+fn name_can_be_passed_as_an_attribute() {
+    assert_eq!(
+        output(
+            r#"<function name="tag"><special name="name" /></function>
+            <print><tag name="passed" /></print>"#
+        ),
+        "passed\n"
+    );
+}
+
+#[test]
+fn built_in_elements_come_first() {
+    assert_eq!(
+        output(
+            r#"<function name="print"><string>from the function</string></function>
+            <print>built-in</print>
+            <print><fn:print /></print>"#
+        ),
+        "built-in\nfrom the function\n"
+    );
+}
+
+#[test]
+fn function_names_are_case_sensitive() {
+    error(
+        &format!("{GREET}<Greet person=\"Dave\" />"),
+        "xmlang::unknown_element",
+    );
+}
+
+#[test]
+fn the_prefix_can_be_changed() {
+    assert_eq!(
+        run(r#"<program function-prefix="my">
+            <function name="add"><string>mine</string></function>
+            <print><my:add /></print>
+            <print><fn:add><int>1</int><int>2</int></fn:add></print>
+        </program>"#)
+        .ok(),
+        "mine\n3\n"
+    );
+}
+
+#[test]
+fn unknown_elements_are_still_errors() {
+    let stderr = error("<gret person=\"Eve\" />", "xmlang::unknown_element");
+    assert!(stderr.contains("gret"), "{stderr}");
+    error("<fn:missing />", "xmlang::undefined_function");
+}
+
+#[test]
+fn functions_defined_later_can_be_used_as_elements() {
+    assert_eq!(
+        output(
+            r#"<function name="outer"><inner /></function>
+            <function name="inner"><string>inner</string></function>
+            <print><outer /></print>"#
+        ),
+        "inner\n"
+    );
+}
+
+#[test]
+fn the_prefix_skips_macros_with_the_same_name() {
+    assert_eq!(
+        output(
+            r#"<function name="shout"><string>function</string></function>
+            <macro name="shout"><string>macro</string></macro>
+            <print><shout /></print>
+            <print><fn:shout /></print>"#
+        ),
+        "macro\nfunction\n"
+    );
+}
@@ -0,0 +1,214 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{output, run, run_with};
+
+#[test]
+fn max_steps_stops_infinite_loops() {
+    run_with(
+        "<program><loop><int>1</int></loop></program>",
+        &["--max-steps", "1000"],
+        "",
+    )
+    .err("xmlang::limit_exceeded::steps");
+}
+
+#[test]
+fn max_call_depth_stops_unbounded_recursion() {
+    run_with(
+        r#"<program><function name="f"><call name="f" /></function><call name="f" /></program>"#,
+        &["--max-call-depth", "50"],
+        "",
+    )
+    .err("xmlang::limit_exceeded::call_depth");
+}
+
+#[test]
+fn timeout_stops_infinite_loops() {
+    run_with(
+        "<program><loop><int>1</int></loop></program>",
+        &["--timeout", "100"],
+        "",
+    )
+    .err("xmlang::limit_exceeded::timeout");
+}
+
+#[test]
+fn timeout_cuts_delay_short() {
+    let started = Instant::now();
+    run_with(
+        r#"<program><delay duration="5000" /></program>"#,
+        &["--timeout", "100"],
+        "",
+    )
+    .err("xmlang::limit_exceeded::timeout");
+
+    assert!(started.elapsed() < Duration::from_secs(3));
+}
+
+#[test]
+fn max_string_length_limits_strings() {
+    run_with(
+        r#"<program><print><mul><string>ab</string><int>100</int></mul></print></program>"#,
+        &["--max-string-length", "50"],
+        "",
+    )
+    .err("xmlang::limit_exceeded::string_length");
+}
+
+#[test]
+fn max_string_length_limits_updated_variables() {
+    for value in ["<int>1234567890</int>", "<string>1234567890</string>"] {
+        run_with(
+            &format!(
+                r#"<program>
+                    <set var="text">{value}</set>
+                    <update var="text" op="concat"><string>1234567890</string></update>
+                </program>"#
+            ),
+            &["--max-string-length", "15"],
+            "",
+        )
+        .err("xmlang::limit_exceeded::string_length");
+    }
+}
+
+#[test]
+fn max_string_length_limits_big_powers_before_computing_them() {
+    run_with(
+        r#"<program><print><pow><bigint>3</bigint><int>4000000000</int></pow></print></program>"#,
+        &["--max-string-length", "1000"],
+        "",
+    )
+    .err("xmlang::limit_exceeded::string_length");
+
+    run_with(
+        r#"<program><print><pow><int>3</int><int>4000000000</int></pow></print></program>"#,
+        &["--max-string-length", "1000", "--overflow", "promote"],
+        "",
+    )
+    .err("xmlang::limit_exceeded::string_length");
+}
+
+#[test]
+fn max_string_length_limits_format_padding_before_allocating_it() {
+    run_with(
+        r#"<program><print><format>{0:99999999999}<int>1</int></format></print></program>"#,
+        &["--max-string-length", "1000"],
+        "",
+    )
+    .err("xmlang::limit_exceeded::string_length");
+
+    run_with(
+        r#"<program><print><format>{0:.99999999999}<float>1.5</float></format></print></program>"#,
+        &["--max-string-length", "1000"],
+        "",
+    )
+    .err("xmlang::limit_exceeded::string_length");
+}
+
+#[test]
+fn limits_are_not_catchable_by_default() {
+    let program = r#"<program>
+        <try>
+            <do><mul><string>ab</string><int>100</int></mul></do>
+            <catch><print>caught <space /> <special name="error_kind" /></print></catch>
+        </try>
+    </program>"#;
+
+    run_with(program, &["--max-string-length", "50"], "")
+        .err("xmlang::limit_exceeded::string_length");
+
+    let printed = run_with(
+        program,
+        &["--max-string-length", "50", "--catchable-limits"],
+        "",
+    )
+    .ok();
+    assert_eq!(printed, "caught LimitExceeded\n");
+}
+
+#[test]
+fn steps_and_timeout_are_never_catchable() {
+    let program = r#"<program>
+        <try>
+            <do><loop><int>1</int></loop></do>
+            <catch><print>caught</print></catch>
+        </try>
+    </program>"#;
+
+    let result = run_with(program, &["--max-steps", "100", "--catchable-limits"], "");
+    assert_eq!(result.stdout, "");
+    result.err("xmlang::limit_exceeded::steps");
+
+    let result = run_with(program, &["--timeout", "100", "--catchable-limits"], "");
+    assert_eq!(result.stdout, "");
+    result.err("xmlang::limit_exceeded::timeout");
+}
+
+#[test]
+fn limit_errors_point_at_the_element_that_exceeded_them() {
+    let stderr = run_with(
+        "<program>\n<print>\n    <mul><string>ab</string><int>100</int></mul>\n</print>\n</program>",
+        &["--max-string-length", "50"],
+        "",
+    )
+    .err("xmlang::limit_exceeded::string_length");
+
+    assert!(stderr.contains(":3:5]"), "wrong location:\n{stderr}");
+}
+
+#[test]
+fn deep_recursion_does_not_copy_the_specials_of_every_caller() {
+    let started = Instant::now();
+    let printed = run_with(
+        r#"<program>
+            <function name="down">
+                <if>
+                    <condition><gt><special name="child:0" /><int>0</int></gt></condition>
+                    <then><call name="down"><sub><special name="child:0" /><int>1</int></sub></call></then>
+                    <else><string>done</string></else>
+                </if>
+            </function>
+            <print><call name="down"><int>5000</int></call></print>
+        </program>"#,
+        &["--max-call-depth", "10000"],
+        "",
+    )
+    .ok();
+
+    assert_eq!(printed, "done\n");
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
+
+#[test]
+fn deeply_nested_programs_do_not_overflow_the_stack() {
+    let nested = format!(
+        "{}<int>1</int>{}",
+        "<block>".repeat(10_000),
+        "</block>".repeat(10_000)
+    );
+
+    let printed = run(&format!(
+        r#"<program>
+            <macro name="wrap"><slot /></macro>
+            <function name="f">{nested}</function>
+            <set var="code"><quote>{nested}</quote></set>
+            <print><call name="f" /></print>
+            <print><wrap>{nested}</wrap></print>
+            <print><eq><get var="code" /><get var="code" /></eq></print>
+        </program>"#
+    ))
+    .ok();
+
+    assert_eq!(printed, "1\n1\ntrue\n");
+}
+
+#[test]
+fn programs_within_the_limits_run_normally() {
+    assert_eq!(
+        output(r#"<print><pow><bigint>2</bigint><int>100</int></pow></print>"#),
+        "1267650600228229401496703205376\n"
+    );
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use common::{assert_prints, output};
+
+#[test]
+fn and_and_or_combine_truthiness() {
+    assert_prints(&[
+        ("<and><true /><false /></and>", "false"),
+        ("<and><int>1</int><string>yes</string></and>", "true"),
+        ("<or><int>0</int><float>0.0</float></or>", "false"),
+        ("<or><false /><int>2</int></or>", "true"),
+    ]);
+}
+
+#[test]
+fn and_stops_at_the_first_false_child() {
+    assert_eq!(
+        output(
+            r#"<set var="n"><int>0</int></set>
+            <print>
+                <and>
+                    <not><eq><get var="n" /><int>0</int></eq></not>
+                    <gt><div><int>100</int><get var="n" /></div><int>10</int></gt>
+                </and>
+            </print>
+            <and><false /><print>not printed</print></and>"#
+        ),
+        "false\n"
+    );
+}
+
+#[test]
+fn or_stops_at_the_first_true_child() {
+    assert_eq!(
+        output("<or><true /><print>not printed</print></or><print>done</print>"),
+        "done\n"
+    );
+}
+
+#[test]
+fn operand_returns_the_deciding_child() {
+    assert_prints(&[
+        (
+            r#"<and operand="true"><int>1</int><string>x</string><int>0</int><int>2</int></and>"#,
+            "0",
+        ),
+        (r#"<and operand="true"><int>1</int><int>2</int></and>"#, "2"),
+        (
+            r#"<or operand="true"><null /><string>anonymous</string></or>"#,
+            "anonymous",
+        ),
+        (
+            r#"<or operand="true"><string>Alice</string><string>anonymous</string></or>"#,
+            "Alice",
+        ),
+        (r#"<or operand="true"><int>0</int><null /></or>"#, "null"),
+    ]);
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use common::{error, output};
+
+#[test]
+fn loop_counts_from_start_to_end() {
+    assert_eq!(
+        output(r#"<loop start="2" end="5"><print><special name="iteration" /></print></loop>"#),
+        "2\n3\n4\n"
+    );
+}
+
+#[test]
+fn step_can_count_down() {
+    assert_eq!(
+        output(
+            r#"<loop start="10" end="0" step="-3" var="i"><print><get var="i" /></print></loop>
+            <loop start="0" end="7" step="3"><print><special name="iteration" /></print></loop>"#
+        ),
+        "10\n7\n4\n1\n0\n3\n6\n"
+    );
+}
+
+#[test]
+fn var_lets_nested_loops_see_the_outer_counter() {
+    assert_eq!(
+        output(
+            r#"<loop start="0" end="2" var="row">
+                <loop start="0" end="3" var="column">
+                    <print newline="false"><format>{row}{column}</format><space /></print>
+                </loop>
+                <print />
+            </loop>"#
+        ),
+        "00 01 02 \n10 11 12 \n"
+    );
+}
+
+#[test]
+fn bounds_can_be_computed_at_runtime() {
+    assert_eq!(
+        output(
+            r#"<set var="size"><int>3</int></set>
+            <loop>
+                <start><sub><get var="size" /><int>1</int></sub></start>
+                <end><int>-1</int></end>
+                <step><string>-1</string></step>
+                <print><special name="iteration" /></print>
+            </loop>"#
+        ),
+        "2\n1\n0\n"
+    );
+}
+
+#[test]
+fn bounds_are_evaluated_once() {
+    assert_eq!(
+        output(
+            r#"<set var="n"><int>3</int></set>
+            <loop>
+                <end><get var="n" /></end>
+                <set var="n"><int>100</int></set>
+                <print><special name="iteration" /></print>
+            </loop>"#
+        ),
+        "0\n1\n2\n"
+    );
+}
+
+#[test]
+fn invalid_bounds_are_errors() {
+    error(
+        r#"<loop end="ten"><print>never</print></loop>"#,
+        "xmlang::invalid_attribute",
+    );
+    error(
+        r#"<loop end="3" step="0"><print>never</print></loop>"#,
+        "xmlang::invalid_attribute",
+    );
+    error(
+        r#"<loop end="3"><end><int>3</int></end><print>never</print></loop>"#,
+        "xmlang::invalid_children",
+    );
+}
+
+#[test]
+fn break_stops_the_nearest_loop_with_a_result() {
+    assert_eq!(
+        output(
+            r#"<print>
+                <loop start="1">
+                    <if>
+                        <condition><gt><mul><special name="iteration" /><special name="iteration" /></mul><int>50</int></gt></condition>
+                        <then><break><special name="iteration" /></break></then>
+                    </if>
+                </loop>
+            </print>"#
+        ),
+        "8\n"
+    );
+}
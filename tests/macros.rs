@@ -0,0 +1,150 @@
+mod common;
+
+use common::{error, output, run};
+
+#[test]
+fn macros_are_replaced_by_their_body() {
+    assert_eq!(
+        output(
+            r#"<unless>
+                <condition><eq><int>1</int><int>2</int></eq></condition>
+                <print>different</print>
+            </unless>
+            <macro name="unless">
+                <if>
+                    <condition><not><slot name="condition" /></not></condition>
+                    <then><slot /></then>
+                </if>
+            </macro>"#
+        ),
+        "different\n"
+    );
+}
+
+#[test]
+fn slots_are_filled_from_attributes_children_and_defaults() {
+    assert_eq!(
+        output(
+            r#"<macro name="repeat">
+                <loop start="0"><end><int><slot name="times"><int>2</int></slot></int></end>
+                    <slot />
+                </loop>
+            </macro>
+            <repeat times="3"><print>hip</print></repeat>
+            <repeat><print>hooray</print></repeat>"#
+        ),
+        "hip\nhip\nhip\nhooray\nhooray\n"
+    );
+}
+
+#[test]
+fn slots_run_each_time_they_appear() {
+    assert_eq!(
+        output(
+            r#"<macro name="twice"><slot /><slot /></macro>
+            <set var="n"><int>0</int></set>
+            <twice><inc var="n" /></twice>
+            <print><get var="n" /></print>"#
+        ),
+        "2\n"
+    );
+}
+
+#[test]
+fn introduced_variables_are_renamed() {
+    assert_eq!(
+        output(
+            r#"<macro name="swap">
+                <set var="temp"><get var="a" /></set>
+                <set var="a"><get var="b" /></set>
+                <set var="b"><get var="temp" /></set>
+            </macro>
+            <set var="temp"><string>mine</string></set>
+            <set var="a"><int>1</int></set>
+            <set var="b"><int>2</int></set>
+            <swap />
+            <print><format>{a} {b} {temp}</format></print>"#
+        ),
+        "2 1 mine\n"
+    );
+}
+
+#[test]
+fn variables_used_before_being_assigned_belong_to_the_caller() {
+    assert_eq!(
+        output(
+            r#"<macro name="bump">
+                <inc var="count" />
+                <set var="count"><mul><get var="count" /><int>10</int></mul></set>
+            </macro>
+            <set var="count"><int>1</int></set>
+            <bump />
+            <print><get var="count" /></print>"#
+        ),
+        "20\n"
+    );
+}
+
+#[test]
+fn set_field_changes_the_callers_variable() {
+    assert_eq!(
+        output(
+            r#"<struct name="Point"><field name="x" type="int" /></struct>
+            <macro name="reset-x"><set-field var="point" name="x"><int>0</int></set-field></macro>
+            <set var="point"><new type="Point" x="5" /></set>
+            <reset-x />
+            <print><get var="point" /></print>"#
+        ),
+        "Point { x: 0 }\n"
+    );
+}
+
+#[test]
+fn format_templates_use_renamed_variables() {
+    assert_eq!(
+        output(
+            r#"<macro name="greet">
+                <set var="name"><slot name="who" /></set>
+                <print><format>Hello, {name}!</format></print>
+            </macro>
+            <set var="name"><string>outer</string></set>
+            <greet who="Ann" />
+            <print><get var="name" /></print>"#
+        ),
+        "Hello, Ann!\nouter\n"
+    );
+}
+
+#[test]
+fn invalid_uses_are_reported_before_running() {
+    for (program, code) in [
+        (
+            r#"<macro name="need"><slot name="value" /></macro><need />"#,
+            "xmlang::missing_slot",
+        ),
+        (
+            r#"<macro name="forever"><forever /></macro><forever />"#,
+            "xmlang::macro_recursion",
+        ),
+        (
+            r#"<macro name="named"><slot name="a" /></macro><named a="1" b="2" />"#,
+            "xmlang::invalid_attribute",
+        ),
+    ] {
+        let result = run(&format!(
+            "<program><print>started</print>{program}</program>"
+        ));
+        assert_eq!(result.stdout, "", "{program}");
+        result.err(code);
+    }
+}
+
+#[test]
+fn errors_point_at_the_use_of_the_macro() {
+    let stderr = error(
+        r#"<macro name="half"><div><slot /><int>0</int></div></macro>
+        <print><half><int>4</int></half></print>"#,
+        "xmlang::division_by_zero",
+    );
+    assert!(stderr.contains("in the expansion of <half>"), "{stderr}");
+}
@@ -0,0 +1,175 @@
+mod common;
+
+use common::{error, output, run};
+
+fn classify(subject: &str) -> String {
+    output(&format!(
+        r#"<print>
+            <match>
+                {subject}
+                <case value="quit">quit</case>
+                <case type="int float" max="0">negative</case>
+                <case min="1" max="9">digit</case>
+                <case type="int"><condition><eq><mod><special name="subject" /><int>2</int></mod><int>0</int></eq></condition>even</case>
+                <case prefix="say "><substring start="4"><special name="subject" /></substring></case>
+                <default><format>other {{subject}}</format></default>
+            </match>
+        </print>"#
+    ))
+}
+
+#[test]
+fn first_matching_case_wins() {
+    assert_eq!(classify("<string>quit</string>"), "quit\n");
+    assert_eq!(classify("<int>-4</int>"), "negative\n");
+    assert_eq!(classify("<float>3.5</float>"), "digit\n");
+    assert_eq!(classify("<int>12</int>"), "even\n");
+    assert_eq!(classify("<string>say hello</string>"), "hello\n");
+    assert_eq!(classify("<int>13</int>"), "other 13\n");
+}
+
+#[test]
+fn value_compares_as_strings() {
+    assert_eq!(
+        output(
+            r#"<print><match><int>42</int><case value="42">int</case></match></print>
+            <print><match><string>42</string><case value="42">string</case></match></print>"#
+        ),
+        "int\nstring\n"
+    );
+}
+
+#[test]
+fn string_ranges_are_lexicographical() {
+    assert_eq!(
+        output(
+            r#"<print>
+                <match>
+                    <string>banana</string>
+                    <case min="a" max="azz">a</case>
+                    <case min="b" max="bzz">b</case>
+                </match>
+            </print>"#
+        ),
+        "b\n"
+    );
+}
+
+#[test]
+fn values_that_cant_be_compared_never_match_ranges() {
+    assert_eq!(
+        output(
+            r#"<print>
+                <match>
+                    <list><int>1</int></list>
+                    <case min="0" max="10">number</case>
+                    <default>list</default>
+                </match>
+            </print>"#
+        ),
+        "list\n"
+    );
+}
+
+#[test]
+fn no_match_without_default_is_null() {
+    assert_eq!(
+        output(r#"<print><match><int>5</int><case value="6">six</case></match></print>"#),
+        "null\n"
+    );
+}
+
+#[test]
+fn default_is_checked_last() {
+    assert_eq!(
+        output(
+            r#"<print>
+                <match>
+                    <int>1</int>
+                    <default>default</default>
+                    <case value="1">one</case>
+                </match>
+            </print>"#
+        ),
+        "one\n"
+    );
+}
+
+#[test]
+fn guard_result_is_available_as_condition() {
+    assert_eq!(
+        output(
+            r#"<print>
+                <match>
+                    <string>hello</string>
+                    <case><condition><length><special name="subject" /></length></condition><special name="condition" /></case>
+                </match>
+            </print>"#
+        ),
+        "5\n"
+    );
+}
+
+#[test]
+fn enum_variants_expose_their_fields() {
+    assert_eq!(
+        output(
+            r#"<enum name="Shape">
+                <variant name="Circle"><field name="radius" type="int" /></variant>
+                <variant name="Square"><field name="side" type="int" /></variant>
+            </enum>
+            <loop start="0" end="2">
+                <set var="shape">
+                    <if>
+                        <condition><eq><special name="iteration" /><int>0</int></eq></condition>
+                        <then><new type="Shape" variant="Circle" radius="2" /></then>
+                        <else><new type="Shape" variant="Square" side="3" /></else>
+                    </if>
+                </set>
+                <print>
+                    <match>
+                        <get var="shape" />
+                        <case variant="Circle"><format>circle {radius}</format></case>
+                        <case variant="Square"><format>square {side}</format></case>
+                    </match>
+                </print>
+            </loop>"#
+        ),
+        "circle 2\nsquare 3\n"
+    );
+}
+
+#[test]
+fn enum_matches_are_checked_before_running() {
+    let check = |cases: &str, code: &str| {
+        let result = run(&format!(
+            r#"<program>
+                <enum name="Light">
+                    <variant name="Red" />
+                    <variant name="Green" />
+                </enum>
+                <print>started</print>
+                <match><new type="Light" variant="Red" />{cases}</match>
+            </program>"#
+        ));
+        assert_eq!(result.stdout, "");
+        result.err(code);
+    };
+
+    check(
+        r#"<case variant="Red">stop</case>"#,
+        "xmlang::non_exhaustive_match",
+    );
+    check(
+        r#"<case variant="Blue">?</case><default>ok</default>"#,
+        "xmlang::undefined_variant",
+    );
+}
+
+#[test]
+fn invalid_range_bounds_are_errors() {
+    error(
+        r#"<match><int>1</int><case min="one">?</case></match>"#,
+        "xmlang::invalid_attribute",
+    );
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use common::{assert_prints, error};
+
+#[test]
+fn powers_and_roots() {
+    assert_prints(&[
+        ("<pow><int>2</int><int>10</int></pow>", "1024"),
+        (
+            "<pow><float>2.0</float><float>0.5</float></pow>",
+            "1.4142135623730951",
+        ),
+        ("<pow><int>2</int><int>-1</int></pow>", "0.5"),
+        ("<sqrt><int>16</int></sqrt>", "4"),
+        ("<cbrt><int>27</int></cbrt>", "3"),
+        ("<exp><int>0</int></exp>", "1"),
+    ]);
+}
+
+#[test]
+fn logarithms() {
+    assert_prints(&[
+        (r#"<log base="2"><int>8</int></log>"#, "3"),
+        ("<log><e /></log>", "1"),
+    ]);
+}
+
+#[test]
+fn trigonometry() {
+    assert_prints(&[
+        ("<sin><div><pi /><int>2</int></div></sin>", "1"),
+        ("<cos><int>0</int></cos>", "1"),
+        (
+            "<atan2><int>1</int><int>1</int></atan2>",
+            "0.7853981633974483",
+        ),
+    ]);
+}
+
+#[test]
+fn rounding() {
+    assert_prints(&[
+        ("<floor><float>2.7</float></floor>", "2"),
+        ("<ceil><float>2.1</float></ceil>", "3"),
+        ("<floor><int>5</int></floor>", "5"),
+        (
+            r#"<round digits="2"><float>3.14159</float></round>"#,
+            "3.14",
+        ),
+        ("<round><float>2.5</float></round>", "3"),
+        ("<round><float>-2.5</float></round>", "-3"),
+        (r#"<round digits="-2"><int>1234</int></round>"#, "1200"),
+        (r#"<round digits="-1"><int>-15</int></round>"#, "-20"),
+        (
+            r#"<round digits="-2"><int>1234567890123456789</int></round>"#,
+            "1234567890123456800",
+        ),
+        (
+            r#"<round digits="-30"><int>9223372036854775807</int></round>"#,
+            "0",
+        ),
+    ]);
+}
+
+#[test]
+fn rounding_ints_past_the_largest_int_overflows() {
+    error(
+        r#"<round digits="-1"><int>9223372036854775807</int></round>"#,
+        "xmlang::integer_overflow",
+    );
+
+    assert_prints(&[
+        (
+            r#"<round digits="-1" mode="saturating"><int>9223372036854775807</int></round>"#,
+            "9223372036854775807",
+        ),
+        (
+            r#"<round digits="-1" mode="promote"><int>9223372036854775807</int></round>"#,
+            "9223372036854775810",
+        ),
+    ]);
+}
+
+#[test]
+fn min_max_and_clamp() {
+    assert_prints(&[
+        (
+            "<min><int>3</int><float>1.5</float><int>2</int></min>",
+            "1.5",
+        ),
+        (
+            "<max><list><int>3</int><int>7</int><int>5</int></list></max>",
+            "7",
+        ),
+        (r#"<clamp min="0" max="10"><int>42</int></clamp>"#, "10"),
+        (r#"<clamp min="0" max="10"><int>-3</int></clamp>"#, "0"),
+    ]);
+
+    error(
+        r#"<clamp min="10" max="0"><int>5</int></clamp>"#,
+        "xmlang::invalid_range",
+    );
+}
+
+#[test]
+fn constants() {
+    assert_prints(&[
+        ("<pi />", "3.141592653589793"),
+        ("<e />", "2.718281828459045"),
+        ("<tau />", "6.283185307179586"),
+        ("<inf />", "inf"),
+        ("<eq><nan /><nan /></eq>", "false"),
+        ("<is-nan><nan /></is-nan>", "true"),
+        ("<is-nan><sqrt><int>-1</int></sqrt></is-nan>", "true"),
+        ("<is-nan><int>1</int></is-nan>", "false"),
+    ]);
+}
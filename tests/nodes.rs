@@ -0,0 +1,179 @@
+mod common;
+
+use common::{assert_prints, error, output, run};
+
+#[test]
+fn quote_returns_code_without_running_it() {
+    assert_eq!(
+        output(
+            r#"<print><quote><print b="2" a="1">Hello!</print></quote></print>
+            <print><quote><foo /><bar /></quote></print>
+            <print><type><quote>hi</quote></type></print>"#
+        ),
+        "<print a=\"1\" b=\"2\">Hello!</print>\n[<foo />, <bar />]\nnode\n"
+    );
+}
+
+#[test]
+fn node_builds_code_from_values() {
+    assert_eq!(
+        output(
+            r#"<set var="message"><string>Hi</string></set>
+            <print>
+                <node tag="loop" start="1">
+                    <attribute name="end"><add><int>2</int><int>3</int></add></attribute>
+                    <quote><print><special name="iteration" /></print></quote>
+                    <null />
+                    <get var="message" />
+                    <list><quote><a /></quote><quote><b /></quote></list>
+                </node>
+            </print>"#
+        ),
+        "<loop end=\"5\" start=\"1\"><print><special name=\"iteration\" /></print>Hi<a /><b /></loop>\n"
+    );
+}
+
+#[test]
+fn nodes_can_be_inspected() {
+    let code = r#"<quote><add a="1"><int>2</int>three</add></quote>"#;
+    assert_prints(&[
+        (&format!("<node-name>{code}</node-name>"), "add"),
+        (
+            &format!(r#"<node-attribute name="a">{code}</node-attribute>"#),
+            "1",
+        ),
+        (
+            &format!(r#"<node-attribute name="b">{code}</node-attribute>"#),
+            "null",
+        ),
+        (&format!("<node-attributes>{code}</node-attributes>"), "[a]"),
+        (
+            &format!("<node-children>{code}</node-children>"),
+            "[<int>2</int>, three]",
+        ),
+        ("<node-text><quote>three</quote></node-text>", "three"),
+        ("<node-text><quote><int /></quote></node-text>", "null"),
+    ]);
+}
+
+#[test]
+fn nodes_are_equal_when_their_code_is() {
+    assert_prints(&[
+        (
+            r#"<eq><quote><foo a="1"><bar /></foo></quote><node tag="foo" a="1"><quote><bar /></quote></node></eq>"#,
+            "true",
+        ),
+        (
+            "<eq><quote><foo /></quote><quote><bar /></quote></eq>",
+            "false",
+        ),
+    ]);
+}
+
+#[test]
+fn nodes_can_only_be_added_to_strings_and_lists() {
+    assert_prints(&[
+        (
+            "<add><string>code:</string><quote><foo /></quote></add>",
+            "code:<foo />",
+        ),
+        ("<add><list /><quote><foo /></quote></add>", "[<foo />]"),
+    ]);
+
+    for operation in ["add", "sub", "mul", "div", "mod"] {
+        for operand in [
+            "<true />",
+            "<false />",
+            "<int>1</int>",
+            "<quote><bar /></quote>",
+        ] {
+            error(
+                &format!("<{operation}><quote><foo /></quote>{operand}</{operation}>"),
+                "xmlang::type_mismatch",
+            );
+            error(
+                &format!("<{operation}>{operand}<quote><foo /></quote></{operation}>"),
+                "xmlang::type_mismatch",
+            );
+        }
+    }
+    error(
+        "<max><quote><foo /></quote><int>1</int></max>",
+        "xmlang::type_mismatch",
+    );
+}
+
+#[test]
+fn eval_runs_nodes_in_the_current_environment() {
+    assert_eq!(
+        output(
+            r#"<set var="x"><int>7</int></set>
+            <set var="code"><node tag="mul"><quote><get var="x" /></quote><node tag="int"><int>6</int></node></node></set>
+            <print><eval><get var="code" /></eval></print>
+            <eval><list><quote><set var="x"><int>1</int></set></quote><quote><inc var="x" /></quote></list></eval>
+            <print><get var="x" /></print>"#
+        ),
+        "42\n2\n"
+    );
+}
+
+#[test]
+fn eval_parses_strings_in_a_fresh_environment() {
+    assert_eq!(
+        output(
+            r#"<set var="x"><int>7</int></set>
+            <set var="source"><string><![CDATA[<program><set var="x"><int>1</int></set><get var="x" /></program>]]></string></set>
+            <print><eval env="fresh"><get var="source" /></eval></print>
+            <print><get var="x" /></print>"#
+        ),
+        "1\n7\n"
+    );
+}
+
+#[test]
+fn eval_of_invalid_code_is_an_error() {
+    error(
+        "<eval><string>&lt;a&gt;&lt;/b&gt;</string></eval>",
+        "xmlang::invalid_xml",
+    );
+    error(
+        "<set var=\"x\"><int>1</int></set><eval env=\"fresh\"><quote><inc var=\"x\" /></quote></eval>",
+        "xmlang::undefined_variable",
+    );
+}
+
+#[test]
+fn evaluated_code_is_checked_before_it_runs() {
+    const LIGHT: &str =
+        r#"<enum name="Light"><variant name="Red" /><variant name="Green" /></enum>"#;
+
+    assert_eq!(
+        output(&format!(
+            r#"{LIGHT}
+            <eval><quote>
+                <match><new type="Light" variant="Green" /><case variant="Red">stop</case><case variant="Green">go</case></match>
+            </quote></eval>
+            <print><eval><quote>
+                <match><new type="Light" variant="Green" /><case variant="Red">stop</case><case variant="Green">go</case></match>
+            </quote></eval></print>"#
+        )),
+        "go\n"
+    );
+
+    let result = run(&format!(
+        r#"<program>
+            {LIGHT}
+            <eval><quote>
+                <print>started</print>
+                <match><new type="Light" variant="Red" /><case variant="Nope">?</case><default>ok</default></match>
+            </quote></eval>
+        </program>"#
+    ));
+    assert_eq!(result.stdout, "");
+    result.err("xmlang::undefined_variant");
+
+    error(
+        r#"<eval><string>&lt;block&gt;&lt;print&gt;started&lt;/print&gt;&lt;matches pattern="("&gt;a&lt;/matches&gt;&lt;/block&gt;</string></eval>"#,
+        "xmlang::invalid_pattern",
+    );
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use common::{assert_prints, error, run_with};
+
+const MAX: &str = "<int>9223372036854775807</int>";
+const MIN: &str = "<int>-9223372036854775808</int>";
+
+#[test]
+fn overflow_is_an_error_by_default() {
+    error(
+        &format!("<add>{MAX}<int>1</int></add>"),
+        "xmlang::integer_overflow",
+    );
+    error(
+        &format!("<sub>{MIN}<int>1</int></sub>"),
+        "xmlang::integer_overflow",
+    );
+    error(
+        &format!("<mul>{MAX}<int>2</int></mul>"),
+        "xmlang::integer_overflow",
+    );
+    error(
+        &format!("<div>{MIN}<int>-1</int></div>"),
+        "xmlang::integer_overflow",
+    );
+    error(&format!("<neg>{MIN}</neg>"), "xmlang::integer_overflow");
+    error(&format!("<abs>{MIN}</abs>"), "xmlang::integer_overflow");
+    error(
+        "<pow><int>10</int><int>19</int></pow>",
+        "xmlang::integer_overflow",
+    );
+}
+
+#[test]
+fn overflow_errors_suggest_promoting() {
+    let stderr = error(
+        &format!("<add>{MAX}<int>1</int></add>"),
+        "xmlang::integer_overflow",
+    );
+
+    assert!(stderr.contains(r#"`mode="promote"`"#), "{stderr}");
+    assert!(stderr.contains("`--overflow"), "{stderr}");
+}
+
+#[test]
+fn mode_attribute_changes_what_overflow_does() {
+    assert_prints(&[
+        (
+            &format!(r#"<add mode="wrapping">{MAX}<int>1</int></add>"#),
+            "-9223372036854775808",
+        ),
+        (
+            &format!(r#"<add mode="saturating">{MAX}<int>1</int></add>"#),
+            "9223372036854775807",
+        ),
+        (
+            &format!(r#"<add mode="promote">{MAX}<int>1</int></add>"#),
+            "9223372036854775808",
+        ),
+        (
+            &format!(r#"<mul mode="saturating">{MIN}<int>2</int></mul>"#),
+            "-9223372036854775808",
+        ),
+        (
+            &format!(r#"<neg mode="wrapping">{MIN}</neg>"#),
+            "-9223372036854775808",
+        ),
+        (
+            r#"<pow mode="promote"><int>10</int><int>19</int></pow>"#,
+            "10000000000000000000",
+        ),
+    ]);
+}
+
+#[test]
+fn overflow_option_sets_the_default_mode() {
+    let program = format!("<program><print><add>{MAX}<int>1</int></add></print></program>");
+
+    assert_eq!(
+        run_with(&program, &["--overflow", "wrapping"], "").ok(),
+        "-9223372036854775808\n"
+    );
+    assert_eq!(
+        run_with(&program, &["--overflow", "saturating"], "").ok(),
+        "9223372036854775807\n"
+    );
+}
+
+#[test]
+fn mode_attribute_overrides_the_option() {
+    let program = format!(
+        r#"<program><print><add mode="saturating">{MAX}<int>1</int></add></print></program>"#
+    );
+
+    assert_eq!(
+        run_with(&program, &["--overflow", "wrapping"], "").ok(),
+        "9223372036854775807\n"
+    );
+}
+
+#[test]
+fn results_that_fit_are_not_affected() {
+    assert_prints(&[
+        ("<add><int>1</int><int>2</int></add>", "3"),
+        (
+            r#"<mul mode="wrapping"><int>-4</int><int>5</int></mul>"#,
+            "-20",
+        ),
+        (
+            "<mod><int>-9223372036854775808</int><int>-1</int></mod>",
+            "0",
+        ),
+    ]);
+}
+
+#[test]
+fn invalid_modes_are_reported() {
+    error(
+        r#"<add mode="sometimes"><int>1</int><int>2</int></add>"#,
+        "xmlang::invalid_attribute",
+    );
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use common::{error, output, run_with};
+
+const RANDOM: &str = r#"<program>
+    <print><rand min="1" max="1000000" /></print>
+    <print><rand type="float" /></print>
+    <print><choice><int>1</int><int>2</int><int>3</int><int>4</int></choice></print>
+    <print><shuffle><int>1</int><int>2</int><int>3</int><int>4</int><int>5</int></shuffle></print>
+</program>"#;
+
+#[test]
+fn seed_option_makes_runs_reproducible() {
+    let first = run_with(RANDOM, &["--seed", "42"], "").ok();
+    let second = run_with(RANDOM, &["--seed", "42"], "").ok();
+    let other = run_with(RANDOM, &["--seed", "43"], "").ok();
+
+    assert_eq!(first, second);
+    assert_ne!(first, other);
+}
+
+#[test]
+fn seed_element_reseeds() {
+    let printed = output(
+        r#"<seed value="7" /><print><rand min="0" max="1000000" /></print>
+        <seed><int>7</int></seed><print><rand min="0" max="1000000" /></print>"#,
+    );
+    let lines = printed.lines().collect::<Vec<_>>();
+
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], lines[1]);
+}
+
+#[test]
+fn rand_stays_in_range() {
+    let printed =
+        output(r#"<loop start="0" end="200"><print><rand min="3" max="6" /></print></loop>"#);
+
+    for line in printed.lines() {
+        let value = line.parse::<i64>().unwrap();
+        assert!((3..6).contains(&value), "{value} is out of range");
+    }
+}
+
+#[test]
+fn rand_rejects_empty_range() {
+    error(r#"<rand min="5" max="5" />"#, "xmlang::invalid_range");
+}
+
+#[test]
+fn rand_rejects_bounds_that_arent_numbers() {
+    error(r#"<rand min="one" max="5" />"#, "xmlang::invalid_attribute");
+    error(r#"<rand max="1.5" />"#, "xmlang::invalid_attribute");
+    error(
+        r#"<rand type="float" max="big" />"#,
+        "xmlang::invalid_attribute",
+    );
+}
+
+#[test]
+fn seed_rejects_negative_seeds() {
+    error(r#"<seed value="-1" />"#, "xmlang::invalid_attribute");
+    error("<seed><int>-1</int></seed>", "xmlang::conversion_failed");
+}
+
+#[test]
+fn choice_evaluates_only_the_chosen_child() {
+    let printed = output(r#"<choice><print>a</print><print>b</print><print>c</print></choice>"#);
+
+    assert_eq!(printed.lines().count(), 1);
+}
+
+#[test]
+fn shuffle_keeps_every_item() {
+    let printed = output(
+        r#"<set var="items"><shuffle><list><int>1</int><int>2</int><int>3</int></list></shuffle></set>
+        <print><get var="items" /></print>"#,
+    );
+    let mut items = printed
+        .trim()
+        .trim_matches(['[', ']'])
+        .split(", ")
+        .collect::<Vec<_>>();
+    items.sort();
+
+    assert_eq!(items, ["1", "2", "3"]);
+}
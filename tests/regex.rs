@@ -0,0 +1,76 @@
+mod common;
+
+use common::{assert_prints, run};
+
+#[test]
+fn matches_checks_for_a_match() {
+    assert_prints(&[
+        (r#"<matches pattern="^\d+$">12345</matches>"#, "true"),
+        (r#"<matches pattern="^\d+$">123abc</matches>"#, "false"),
+        (
+            r#"<matches pattern="&lt;b&gt;">a &lt;b&gt; tag</matches>"#,
+            "true",
+        ),
+    ]);
+}
+
+#[test]
+fn find_returns_the_captures() {
+    assert_prints(&[
+        (
+            r#"<find pattern="(\d{4})-(\d\d)-(\d\d)">Released on 2024-05-01</find>"#,
+            "[2024-05-01, 2024, 05, 01]",
+        ),
+        (
+            r#"<find pattern="\[(\w+)\]" all="true">[db] failed, [api] slow</find>"#,
+            "[[[db], db], [[api], api]]",
+        ),
+        (r#"<find pattern="a(x)?">a</find>"#, "[a, null]"),
+        (r#"<find pattern="\d">none</find>"#, "null"),
+    ]);
+}
+
+#[test]
+fn replace_regex_expands_groups() {
+    assert_prints(&[
+        (
+            r#"<replace-regex pattern="(\d{4})-(\d\d)-(\d\d)" replacement="$3/$2/$1">2024-05-01</replace-regex>"#,
+            "01/05/2024",
+        ),
+        (
+            r#"<replace-regex pattern="\d" count="2"><string>a1b2c3</string><string>#</string></replace-regex>"#,
+            "a#b#c3",
+        ),
+        (
+            r#"<replace-regex pattern="(?P<word>\w+)" replacement="${word}!">hi</replace-regex>"#,
+            "hi!",
+        ),
+    ]);
+}
+
+#[test]
+fn split_regex_splits_by_matches() {
+    assert_prints(&[(
+        r#"<split-regex pattern="\s*[,;]\s*">a, b;c ,d</split-regex>"#,
+        "[a, b, c, d]",
+    )]);
+}
+
+#[test]
+fn invalid_patterns_are_reported_before_running() {
+    let output = run(r#"<program>
+            <print>started</print>
+            <if><condition><false /></condition><then><matches pattern="(">x</matches></then></if>
+        </program>"#);
+
+    assert_eq!(output.stdout, "");
+    output.err("xmlang::invalid_pattern");
+}
+
+#[test]
+fn patterns_inside_quotes_are_not_checked() {
+    assert_prints(&[(
+        r#"<node-name><quote><matches pattern="(" /></quote></node-name>"#,
+        "matches",
+    )]);
+}
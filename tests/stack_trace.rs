@@ -0,0 +1,62 @@
+mod common;
+
+use common::{error, output};
+
+const FUNCTIONS: &str = r#"
+    <function name="inner"><div><int>1</int><special name="child:0" /></div></function>
+    <function name="outer"><call name="inner"><int><special name="x" /></int></call></function>
+"#;
+
+#[test]
+fn uncaught_errors_show_every_call() {
+    let stderr = error(
+        &format!(r#"{FUNCTIONS}<call name="outer" x="0" />"#),
+        "xmlang::division_by_zero",
+    );
+
+    let inner = stderr.find("in inner(0)").expect("no frame for `inner`");
+    let outer = stderr.find("in outer(x=0)").expect("no frame for `outer`");
+    assert!(inner < outer, "the innermost call should come first");
+    assert_eq!(stderr.matches("called here").count(), 2);
+}
+
+#[test]
+fn errors_outside_functions_have_no_trace() {
+    let stderr = error(
+        "<div><int>1</int><int>0</int></div>",
+        "xmlang::division_by_zero",
+    );
+
+    assert!(!stderr.contains("called here"));
+}
+
+#[test]
+fn stack_special_lists_the_calls() {
+    assert_eq!(
+        output(&format!(
+            r#"{FUNCTIONS}
+            <try>
+                <do><call name="outer" x="0" /></do>
+                <catch><print><special name="stack" /></print></catch>
+            </try>"#
+        )),
+        "[inner(0), outer(x=0)]\n"
+    );
+}
+
+#[test]
+fn caught_errors_do_not_leave_a_trace_behind() {
+    let stderr = error(
+        &format!(
+            r#"{FUNCTIONS}
+            <try>
+                <do><call name="outer" x="0" /></do>
+                <catch />
+            </try>
+            <div><int>1</int><int>0</int></div>"#
+        ),
+        "xmlang::division_by_zero",
+    );
+
+    assert!(!stderr.contains("called here"));
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use common::assert_prints;
+
+#[test]
+fn length_counts_graphemes() {
+    assert_prints(&[
+        ("<length>Hello!</length>", "6"),
+        ("<length>héllo 👍🏽</length>", "7"),
+        ("<len>🇵🇱</len>", "1"),
+        (
+            "<length><list><int>1</int><int>2</int></list></length>",
+            "2",
+        ),
+    ]);
+}
+
+#[test]
+fn substring_accepts_negative_and_clamped_indices() {
+    assert_prints(&[
+        (
+            r#"<substring start="7">Hello, world!</substring>"#,
+            "world!",
+        ),
+        (
+            r#"<substring start="0" end="-1">Hello, world!</substring>"#,
+            "Hello, world",
+        ),
+        (
+            r#"<substring start="3" end="100">abcdef</substring>"#,
+            "def",
+        ),
+        (r#"<substring start="4" end="2">abcdef</substring>"#, ""),
+        (
+            "<substring><string>a👍🏽b</string><int>1</int><int>2</int></substring>",
+            "👍🏽",
+        ),
+    ]);
+}
+
+#[test]
+fn searching() {
+    assert_prints(&[
+        (
+            "<index-of><string>Hello, world!</string><string>world</string></index-of>",
+            "7",
+        ),
+        (
+            "<index-of><string>é👍🏽x</string><string>x</string></index-of>",
+            "2",
+        ),
+        (
+            "<index-of><string>abc</string><string>z</string></index-of>",
+            "null",
+        ),
+        (
+            "<starts-with><string>Hello</string><string>He</string></starts-with>",
+            "true",
+        ),
+        (
+            "<ends-with><string>Hello</string><string>LO</string></ends-with>",
+            "false",
+        ),
+        (
+            "<contains><string>Hello</string><string>ell</string></contains>",
+            "true",
+        ),
+    ]);
+}
+
+#[test]
+fn replacing_and_splitting() {
+    assert_prints(&[
+        (
+            "<replace><string>a-b-c</string><string>-</string><string>+</string></replace>",
+            "a+b+c",
+        ),
+        (
+            r#"<replace count="1"><string>a-b-c</string><string>-</string><string>+</string></replace>"#,
+            "a+b-c",
+        ),
+        (r#"<split separator=",">a,b,,c</split>"#, "[a, b, , c]"),
+        (
+            r#"<split><string xml:space="preserve">  Hello   world  </string></split>"#,
+            "[Hello, world]",
+        ),
+        (r#"<split separator="">ab</split>"#, "[a, b]"),
+    ]);
+}
+
+#[test]
+fn changing_case_repeating_and_padding() {
+    assert_prints(&[
+        ("<upper>Hello, wörld!</upper>", "HELLO, WÖRLD!"),
+        ("<lower>Hello, wörld!</lower>", "hello, wörld!"),
+        (r#"<repeat count="3">ab</repeat>"#, "ababab"),
+        (r#"<repeat count="-1">ab</repeat>"#, ""),
+        (
+            r#"<pad-start length="5" fill="0"><int>42</int></pad-start>"#,
+            "00042",
+        ),
+        (r#"<pad-end length="6" fill="ab">x</pad-end>"#, "xababa"),
+        (r#"<pad-start length="2">long</pad-start>"#, "long"),
+    ]);
+}
+
+#[test]
+fn characters() {
+    assert_prints(&[
+        (r#"<char-at index="0">Hello</char-at>"#, "H"),
+        (r#"<char-at index="-1">Hello</char-at>"#, "o"),
+        (r#"<char-at index="10">Hello</char-at>"#, "null"),
+        ("<chars>e👍🏽!</chars>", "[e, 👍🏽, !]"),
+        ("<reverse>Hello</reverse>", "olleH"),
+        ("<reverse>a👍🏽b</reverse>", "b👍🏽a"),
+        (
+            "<reverse><list><int>1</int><int>2</int></list></reverse>",
+            "[2, 1]",
+        ),
+    ]);
+}
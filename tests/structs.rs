@@ -0,0 +1,94 @@
+mod common;
+
+use common::{error, output};
+
+const POINT: &str = r#"<struct name="Point">
+    <field name="x" type="float" />
+    <field name="y" type="float"><float>0</float></field>
+</struct>"#;
+
+fn with_point(body: &str) -> String {
+    output(&format!("{POINT}{body}"))
+}
+
+#[test]
+fn new_fills_fields_from_attributes_children_and_defaults() {
+    assert_eq!(
+        with_point(
+            r#"<print><new type="Point" x="1.5" /></print>
+            <print><new type="Point"><field name="x"><int>2</int></field><field name="y"><int>3</int></field></new></print>
+            <print><type><new type="Point" x="1" /></type></print>"#
+        ),
+        "Point { x: 1.5, y: 0 }\nPoint { x: 2, y: 3 }\nPoint\n"
+    );
+}
+
+#[test]
+fn fields_can_be_read_and_changed() {
+    assert_eq!(
+        with_point(
+            r#"<set var="p"><new type="Point" x="1" /></set>
+            <set var="copy"><get var="p" /></set>
+            <set-field var="p" name="y"><string>4.5</string></set-field>
+            <print><get-field name="y"><get var="p" /></get-field></print>
+            <print><get var="copy" /></print>
+            <print><set-field name="x"><get var="p" /><int>7</int></set-field></print>"#
+        ),
+        "4.5\nPoint { x: 1, y: 0 }\nPoint { x: 7, y: 4.5 }\n"
+    );
+}
+
+#[test]
+fn structs_are_equal_when_their_fields_are() {
+    assert_eq!(
+        with_point(
+            r#"<print><eq><new type="Point" x="1" /><new type="Point" x="1" y="0" /></eq></print>
+            <print><eq><new type="Point" x="1" /><new type="Point" x="2" /></eq></print>"#
+        ),
+        "true\nfalse\n"
+    );
+}
+
+#[test]
+fn structs_can_only_be_added_to_strings_and_lists() {
+    assert_eq!(
+        with_point(
+            r#"<print><add><string>at:</string><new type="Point" x="1" /></add></print>
+            <print><length><add><list /><new type="Point" x="1" /></add></length></print>"#
+        ),
+        "at:Point { x: 1, y: 0 }\n1\n"
+    );
+
+    for operation in ["add", "sub", "mul", "div", "mod"] {
+        for operand in ["<true />", "<false />", "<int>1</int>"] {
+            let stderr = error(
+                &format!(
+                    r#"{POINT}<{operation}><new type="Point" x="1" />{operand}</{operation}>"#
+                ),
+                "xmlang::type_mismatch",
+            );
+            assert!(!stderr.contains("panicked"), "{stderr}");
+        }
+    }
+}
+
+#[test]
+fn invalid_fields_are_errors() {
+    error(
+        &format!(r#"{POINT}<new type="Point" x="1" z="2" />"#),
+        "xmlang::undefined_field",
+    );
+    error(
+        &format!(r#"{POINT}<new type="Point" />"#),
+        "xmlang::missing_field",
+    );
+    error(
+        &format!(r#"{POINT}<new type="Point" x="far" />"#),
+        "xmlang::invalid_field_type",
+    );
+    error(
+        &format!(r#"{POINT}<get-field name="z"><new type="Point" x="1" /></get-field>"#),
+        "xmlang::undefined_field",
+    );
+    error(r#"<new type="Missing" />"#, "xmlang::undefined_struct");
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use common::{error, output};
+
+#[test]
+fn inc_and_dec_return_the_new_value() {
+    assert_eq!(
+        output(
+            r#"<set var="n"><int>5</int></set>
+            <print><inc var="n" /></print>
+            <print><dec var="n" by="3" /></print>
+            <print><inc var="n"><float>0.5</float></inc></print>
+            <print><get var="n" /></print>"#
+        ),
+        "6\n3\n3.5\n3.5\n"
+    );
+}
+
+#[test]
+fn update_applies_the_operation_with_the_variable_on_the_left() {
+    assert_eq!(
+        output(
+            r#"<set var="n"><int>20</int></set>
+            <update var="n" op="sub"><int>2</int></update>
+            <update var="n" op="div"><int>3</int></update>
+            <update var="n" op="mul"><int>4</int></update>
+            <update var="n" op="mod"><int>7</int></update>
+            <print><get var="n" /></print>
+            <update var="n" op="concat"><int>0</int></update>
+            <print><add><get var="n" /><int>1</int></add></print>"#
+        ),
+        "3\n301\n"
+    );
+}
+
+#[test]
+fn strings_and_lists_are_appended_to() {
+    assert_eq!(
+        output(
+            r#"<set var="report"><string /></set>
+            <set var="items"><list /></set>
+            <loop var="i" start="1" end="4">
+                <update var="report" op="concat"><format>#{i};</format></update>
+                <update var="items" op="add"><get var="i" /></update>
+            </loop>
+            <update var="items" op="concat"><list><int>8</int><int>9</int></list></update>
+            <print><get var="report" /></print>
+            <print><get var="items" /></print>"#
+        ),
+        "#1;#2;#3;\n[1, 2, 3, 8, 9]\n"
+    );
+}
+
+#[test]
+fn update_returns_null() {
+    assert_eq!(
+        output(
+            r#"<set var="text"><string>a</string></set>
+            <print><update var="text" op="add"><string>b</string></update></print>"#
+        ),
+        "null\n"
+    );
+}
+
+#[test]
+fn failed_operations_keep_the_old_value() {
+    assert_eq!(
+        output(
+            r#"<set var="n"><int>9223372036854775807</int></set>
+            <try>
+                <do><inc var="n" /></do>
+                <catch><print><special name="error_kind" /></print></catch>
+            </try>
+            <print><get var="n" /></print>
+            <set var="items"><list><int>1</int></list></set>
+            <try>
+                <do><update var="items" op="div"><int>2</int></update></do>
+                <catch><print><special name="error_kind" /></print></catch>
+            </try>
+            <print><get var="items" /></print>"#
+        ),
+        "IntegerOverflow\n9223372036854775807\nIncompatibleTypes\n[1]\n"
+    );
+}
+
+#[test]
+fn overflow_mode_can_be_chosen() {
+    assert_eq!(
+        output(
+            r#"<set var="n"><int>9223372036854775807</int></set>
+            <print><inc var="n" mode="wrapping" /></print>
+            <print><dec var="n" mode="saturating" /></print>"#
+        ),
+        "-9223372036854775808\n-9223372036854775808\n"
+    );
+}
+
+#[test]
+fn invalid_updates_are_errors() {
+    error(r#"<inc var="missing" />"#, "xmlang::undefined_variable");
+    error(
+        r#"<update var="missing" op="add"><int>1</int></update>"#,
+        "xmlang::undefined_variable",
+    );
+    error(
+        r#"<set var="n"><int>1</int></set><update var="n" op="pow"><int>2</int></update>"#,
+        "xmlang::invalid_attribute",
+    );
+    error(
+        r#"<set var="n"><int>1</int></set><inc var="n" by="lots" />"#,
+        "xmlang::invalid_attribute",
+    );
+}
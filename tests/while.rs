@@ -0,0 +1,155 @@
+mod common;
+
+use common::{error, output};
+
+#[test]
+fn while_checks_the_condition_before_each_iteration() {
+    assert_eq!(
+        output(
+            r#"<set var="n"><int>0</int></set>
+            <while>
+                <condition><lt><get var="n" /><int>3</int></lt></condition>
+                <do>
+                    <print><special name="iteration" /></print>
+                    <inc var="n" />
+                </do>
+            </while>
+            <while>
+                <condition><false /></condition>
+                <do><print>not printed</print></do>
+            </while>"#
+        ),
+        "0\n1\n2\n"
+    );
+}
+
+#[test]
+fn do_while_runs_at_least_once() {
+    assert_eq!(
+        output(
+            r#"<do-while>
+                <condition><false /></condition>
+                <do><print>once</print></do>
+            </do-while>"#
+        ),
+        "once\n"
+    );
+}
+
+#[test]
+fn break_stops_the_loop_with_a_result() {
+    assert_eq!(
+        output(
+            r#"<print>
+                <while>
+                    <condition><true /></condition>
+                    <do>
+                        <if>
+                            <condition><eq><special name="iteration" /><int>4</int></eq></condition>
+                            <then><break><string>stopped</string></break></then>
+                        </if>
+                    </do>
+                </while>
+            </print>"#
+        ),
+        "stopped\n"
+    );
+}
+
+#[test]
+fn continue_checks_the_condition_again() {
+    assert_eq!(
+        output(
+            r#"<set var="n"><int>0</int></set>
+            <while>
+                <condition><lt><get var="n" /><int>5</int></lt></condition>
+                <do>
+                    <inc var="n" />
+                    <if>
+                        <condition><eq><mod><get var="n" /><int>2</int></mod><int>0</int></eq></condition>
+                        <then><continue /></then>
+                    </if>
+                    <print><get var="n" /></print>
+                </do>
+            </while>"#
+        ),
+        "1\n3\n5\n"
+    );
+}
+
+#[test]
+fn continue_outside_of_a_loop_is_an_error() {
+    error("<continue />", "xmlang::continue_outside_loop");
+}
+
+#[test]
+fn break_outside_of_a_loop_works_like_return() {
+    assert_eq!(
+        output(
+            r#"<print><block><break><string>left</string></break><string>not reached</string></block></print>
+            <function name="first"><break><special name="child:0" /></break><string>not reached</string></function>
+            <print><call name="first"><int>1</int></call></print>
+            <break />
+            <print>not printed</print>"#
+        ),
+        "left\n1\n"
+    );
+}
+
+#[test]
+fn break_in_a_function_does_not_stop_the_callers_loop() {
+    assert_eq!(
+        output(
+            r#"<function name="stop"><break /><print>not printed</print></function>
+            <loop start="0" end="3">
+                <call name="stop" />
+                <print><special name="iteration" /></print>
+            </loop>"#
+        ),
+        "0\n1\n2\n"
+    );
+}
+
+#[test]
+fn continue_in_a_function_does_not_skip_the_callers_iteration() {
+    error(
+        r#"<function name="skip"><continue /></function>
+        <while>
+            <condition><true /></condition>
+            <do><call name="skip" /><print>not skipped</print></do>
+        </while>"#,
+        "xmlang::continue_outside_loop",
+    );
+}
+
+#[test]
+fn break_in_a_block_after_a_loop_only_leaves_the_block() {
+    assert_eq!(
+        output(
+            r#"<loop start="0" end="1"><print>looped</print></loop>
+            <print><block><break><string>left</string></break></block></print>
+            <print>after</print>"#
+        ),
+        "looped\nleft\nafter\n"
+    );
+}
+
+#[test]
+fn loops_inside_a_function_can_still_be_stopped() {
+    assert_eq!(
+        output(
+            r#"<function name="first-even">
+                <loop start="1">
+                    <if>
+                        <condition><eq><mod><special name="iteration" /><int>2</int></mod><int>0</int></eq></condition>
+                        <then><break><special name="iteration" /></break></then>
+                    </if>
+                </loop>
+            </function>
+            <loop start="0" end="2">
+                <print><first-even /></print>
+            </loop>"#
+        ),
+        "2\n2\n"
+    );
+}
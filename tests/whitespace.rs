@@ -0,0 +1,68 @@
+mod common;
+
+use common::{output, run};
+
+#[test]
+fn whitespace_is_trimmed_by_default() {
+    assert_eq!(
+        output(r#"<set var="x">wonderful</set><print>Hello <get var="x" /> world!</print>"#),
+        "Hellowonderfulworld!\n"
+    );
+}
+
+#[test]
+fn preserve_keeps_whitespace_in_print() {
+    assert_eq!(
+        run(r#"<program xml:space="preserve">
+            <set var="x">wonderful</set>
+            <print>Hello <get var="x" /> world!</print>
+            <print><get var="x" /> <get var="x" /></print>
+        </program>"#)
+        .ok(),
+        "Hello wonderful world!\nwonderful wonderful\n"
+    );
+}
+
+#[test]
+fn preserve_keeps_whitespace_in_string() {
+    assert_eq!(
+        output(r#"<print><string xml:space="preserve">  a <int>1</int> b  </string></print>"#),
+        "  a 1 b  \n"
+    );
+}
+
+#[test]
+fn preserve_ignores_indentation() {
+    assert_eq!(
+        run(r#"<program xml:space="preserve">
+            <print>
+                <int>1</int>
+            </print>
+        </program>"#)
+        .ok(),
+        "1\n"
+    );
+}
+
+#[test]
+fn preserve_ignores_whitespace_between_other_children() {
+    assert_eq!(
+        run(r#"<program xml:space="preserve">
+            <print><add><int>1</int> <int>2</int></add></print>
+            <if> <condition><true /></condition> <then><print>yes</print></then> </if>
+        </program>"#)
+        .ok(),
+        "3\nyes\n"
+    );
+}
+
+#[test]
+fn default_switches_back_to_trimming() {
+    assert_eq!(
+        run(r#"<program xml:space="preserve">
+            <print xml:space="default">a <int>1</int> b</print>
+        </program>"#)
+        .ok(),
+        "a1b\n"
+    );
+}
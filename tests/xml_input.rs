@@ -0,0 +1,49 @@
+mod common;
+
+use common::{error, output, run};
+
+#[test]
+fn cdata_is_kept_literally() {
+    assert_eq!(
+        output("<print><![CDATA[<b>bold</b> & more]]></print>"),
+        "<b>bold</b> & more\n"
+    );
+}
+
+#[test]
+fn cdata_whitespace_is_not_trimmed() {
+    assert_eq!(
+        output("<print>[<![CDATA[  padded  ]]>]</print>"),
+        "[  padded  ]\n"
+    );
+}
+
+#[test]
+fn predefined_entities_are_resolved() {
+    assert_eq!(
+        output("<print>&lt;tag&gt; &amp; &quot;quoted&quot; &apos;single&apos;</print>"),
+        "<tag> & \"quoted\" 'single'\n"
+    );
+}
+
+#[test]
+fn character_references_are_resolved() {
+    assert_eq!(output("<print>&#72;&#x69;&#x1F600;</print>"), "Hi😀\n");
+}
+
+#[test]
+fn entities_in_attributes_are_resolved() {
+    let stderr = error(r#"<throw message="&lt;&amp;&#62;" />"#, "xmlang::thrown");
+
+    assert!(stderr.contains("<&>"));
+}
+
+#[test]
+fn unknown_entities_are_reported() {
+    error("<print>&nope;</print>", "xmlang::unknown_entity");
+}
+
+#[test]
+fn invalid_xml_is_reported() {
+    run("<program><print>hi</program>").err("xmlang::invalid_xml");
+}